@@ -0,0 +1,98 @@
+use fast_search::trie::enums::{Allocation, Case, Match};
+use fast_search::trie::tree::Tree;
+use std::time::{Duration, Instant};
+
+const WORDS: usize = 200_000;
+const ROUNDS: usize = 15;
+
+//deterministic lowercase words with a skewed length distribution, roughly dictionary shaped
+fn words() -> Vec<String> {
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed
+    };
+    (0..WORDS)
+        .map(|_| {
+            let len = 3 + (next() % 10) as usize;
+            (0..len)
+                .map(|_| (b'a' + (next() % 26) as u8) as char)
+                .collect()
+        })
+        .collect()
+}
+
+fn best_of<F: FnMut()>(mut f: F) -> Duration {
+    (0..ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn report(name: &str, elapsed: Duration) {
+    let per_sec = WORDS as f64 / elapsed.as_secs_f64();
+    println!("{:<10} {:>10.2?} {:>14.0} keys/s", name, elapsed, per_sec);
+}
+
+fn main() {
+    let words = words();
+    let mut sorted = words.clone();
+    sorted.sort();
+
+    for &(name, allocation) in &[("heap", Allocation::Heap), ("arena", Allocation::Arena)] {
+        println!("{}", name);
+        report(
+            "insert",
+            best_of(|| {
+                let mut tree =
+                    Tree::with_allocation(Match::Exact, Case::Sensitive, false, allocation);
+                for word in &words {
+                    tree.add(word);
+                }
+            }),
+        );
+
+        let mut tree = Tree::with_allocation(Match::Exact, Case::Sensitive, false, allocation);
+        for word in &words {
+            tree.add(word);
+        }
+        report(
+            "lookup",
+            best_of(|| {
+                for word in &words {
+                    assert!(tree.exists(word));
+                }
+            }),
+        );
+
+        report(
+            "sorted add",
+            best_of(|| {
+                let mut tree =
+                    Tree::with_allocation(Match::Exact, Case::Sensitive, false, allocation);
+                for word in &sorted {
+                    tree.add(word);
+                }
+            }),
+        );
+        report(
+            "bulk load",
+            best_of(|| {
+                Tree::from_sorted_iter(Match::Exact, Case::Sensitive, false, allocation, &sorted)
+                    .unwrap();
+            }),
+        );
+        report(
+            "par build",
+            best_of(|| {
+                Tree::par_build(Match::Exact, Case::Sensitive, false, allocation, &words);
+            }),
+        );
+    }
+}
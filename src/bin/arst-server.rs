@@ -0,0 +1,373 @@
+use fast_search::trie::enums::Allocation;
+use fast_search::trie::tree::Tree;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process;
+use std::sync::{mpsc, Arc, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+const USAGE: &str = "usage: arst-server <tree> [--port <n>] [--reload-ms <n>] [--workers <n>]
+
+serves a tree saved by `arst build` on 127.0.0.1 (default port 7878)
+the file is checked for changes every reload-ms milliseconds (default 1000)
+workers connections are answered at once (default 16), the rest wait to be accepted
+
+  GET /exists?q=<word>                    {\"exists\": true}
+  GET /complete?q=<prefix>&limit=<n>      {\"completions\": [...]}
+  GET /fuzzy?q=<word>&distance=<n>        {\"matches\": [...]}, distance at most 3";
+
+//longest request or header line accepted, in bytes
+const MAX_LINE: usize = 8 * 1024;
+//header lines read before the request is turned away
+const MAX_HEADERS: usize = 100;
+//time a client has for its whole request, however slowly the bytes come
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+//largest fuzzy distance served, the nodes a fuzzy search visits grow quickly with it and near
+//the length of q it visits most of the tree
+const MAX_DISTANCE: usize = 3;
+
+//the tree being served, replaced as a whole when the file changes
+//requests hold on to the Arc so a reload never waits for them
+struct Served {
+    path: String,
+    tree: RwLock<Arc<Tree>>,
+}
+
+fn load(path: &str) -> io::Result<Tree> {
+    Tree::load(BufReader::new(File::open(path)?), Allocation::Arena)
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+//poll the file and swap in the new tree once it loads cleanly
+//a file caught halfway through being written fails to load and is retried on the next tick
+fn reload(served: &Served, interval: Duration) {
+    let mut loaded = modified(&served.path);
+    loop {
+        thread::sleep(interval);
+        let current = modified(&served.path);
+        if current.is_none() || current == loaded {
+            continue;
+        }
+        match load(&served.path) {
+            Ok(tree) => {
+                *served.tree.write().unwrap() = Arc::new(tree);
+                loaded = current;
+                eprintln!("reloaded {}", served.path);
+            }
+            Err(e) => eprintln!("keeping the previous tree, {}: {}", served.path, e),
+        }
+    }
+}
+
+//decode a query string component, + is a space
+fn decode(component: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(component.len());
+    let mut input = component.bytes();
+    while let Some(byte) = input.next() {
+        bytes.push(match byte {
+            b'+' => b' ',
+            b'%' => {
+                let hex = [input.next()?, input.next()?];
+                u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?
+            }
+            _ => byte,
+        });
+    }
+    String::from_utf8(bytes).ok()
+}
+
+fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn json_list(values: Vec<String>) -> String {
+    let values = values.iter().map(|value| json_string(value)).collect::<Vec<_>>();
+    format!("[{}]", values.join(", "))
+}
+
+fn error(status: &'static str, message: &str) -> (&'static str, String) {
+    (status, format!("{{\"error\": {}}}", json_string(message)))
+}
+
+//status line and json body for a request target such as /complete?q=ban&limit=5
+fn respond(tree: &Tree, target: &str) -> (&'static str, String) {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let mut params = Vec::new();
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+        match (decode(name), decode(value)) {
+            (Some(name), Some(value)) => params.push((name, value)),
+            _ => return error("400 Bad Request", "malformed query string"),
+        }
+    }
+    let param = |name: &str| params.iter().find(|(param, _)| param == name).map(|(_, value)| value.as_str());
+    let number = |name: &str, default: usize| match param(name) {
+        Some(value) => value.parse().ok(),
+        None => Some(default),
+    };
+
+    let q = match param("q") {
+        Some(q) => q,
+        None if ["/exists", "/complete", "/fuzzy"].contains(&path) => return error("400 Bad Request", "missing q"),
+        None => return error("404 Not Found", "not found"),
+    };
+    match (path, number("limit", 10), number("distance", 1)) {
+        ("/exists", _, _) => ("200 OK", format!("{{\"exists\": {}}}", tree.exists(q))),
+        ("/complete", Some(limit), _) => (
+            "200 OK",
+            format!("{{\"completions\": {}}}", json_list(tree.auto_complete(q, limit))),
+        ),
+        ("/fuzzy", _, Some(distance)) if distance > MAX_DISTANCE => {
+            error("400 Bad Request", &format!("distance is at most {}", MAX_DISTANCE))
+        }
+        ("/fuzzy", _, Some(distance)) => (
+            "200 OK",
+            format!("{{\"matches\": {}}}", json_list(tree.fuzzy(q, distance))),
+        ),
+        ("/complete", None, _) | ("/fuzzy", _, None) => error("400 Bad Request", "limit and distance must be numbers"),
+        _ => error("404 Not Found", "not found"),
+    }
+}
+
+//one line including its \n, None once it runs past MAX_LINE
+//timeout is given the time left before deadline ahead of every read, so a line trickling in
+//a byte at a time still ends there
+fn read_line<R: BufRead>(
+    reader: &mut R,
+    deadline: Instant,
+    timeout: &dyn Fn(Duration) -> io::Result<()>,
+) -> io::Result<Option<Vec<u8>>> {
+    let mut line = Vec::new();
+    loop {
+        match deadline.checked_duration_since(Instant::now()) {
+            Some(left) if !left.is_zero() => timeout(left)?,
+            _ => return Err(io::Error::new(io::ErrorKind::TimedOut, "request took too long")),
+        }
+        let available = reader.fill_buf()?;
+        if available.is_empty() {
+            return Ok(Some(line));
+        }
+        let (taken, ended) = match available.iter().position(|&byte| byte == b'\n') {
+            Some(end) => (end + 1, true),
+            None => (available.len(), false),
+        };
+        if line.len() + taken > MAX_LINE {
+            return Ok(None);
+        }
+        line.extend_from_slice(&available[..taken]);
+        reader.consume(taken);
+        if ended {
+            return Ok(Some(line));
+        }
+    }
+}
+
+//the request line, or the response turning the request away when it is too large
+//the headers are read to the end so the client sees its whole request was read
+fn read_request<R: BufRead>(
+    reader: &mut R,
+    deadline: Instant,
+    timeout: &dyn Fn(Duration) -> io::Result<()>,
+) -> io::Result<Result<String, (&'static str, String)>> {
+    let request = match read_line(reader, deadline, timeout)? {
+        Some(request) => String::from_utf8_lossy(&request).into_owned(),
+        None => return Ok(Err(error("400 Bad Request", "request line too long"))),
+    };
+    for _ in 0..MAX_HEADERS {
+        match read_line(reader, deadline, timeout)? {
+            Some(header) if header.len() > 2 => {}
+            Some(_) => return Ok(Ok(request)),
+            None => return Ok(Err(error("431 Request Header Fields Too Large", "header line too long"))),
+        }
+    }
+    Ok(Err(error("431 Request Header Fields Too Large", "too many headers")))
+}
+
+//one request per connection, only the request line matters
+fn handle(stream: TcpStream, served: &Served) -> io::Result<()> {
+    let deadline = Instant::now() + REQUEST_TIMEOUT;
+    let mut reader = BufReader::new(&stream);
+    let timeout = |left| stream.set_read_timeout(Some(left));
+    let (status, body) = match read_request(&mut reader, deadline, &timeout)? {
+        Ok(request) => {
+            let mut parts = request.split_whitespace();
+            match (parts.next(), parts.next()) {
+                (Some("GET"), Some(target)) => {
+                    let tree = served.tree.read().unwrap().clone();
+                    respond(&tree, target)
+                }
+                (Some(_), Some(_)) => error("405 Method Not Allowed", "only GET is supported"),
+                _ => error("400 Bad Request", "malformed request"),
+            }
+        }
+        Err(response) => response,
+    };
+
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let fail = |message: String| -> ! {
+        eprintln!("arst-server: {}\n\n{}", message, USAGE);
+        process::exit(2);
+    };
+    let option = |name: &str, default: u64| -> u64 {
+        match args.iter().position(|arg| arg == name) {
+            Some(i) => match args.get(i + 1).map(|value| value.parse()) {
+                Some(Ok(value)) => value,
+                _ => fail(format!("{} expects a number", name)),
+            },
+            None => default,
+        }
+    };
+    let (port, interval) = (option("--port", 7878), option("--reload-ms", 1000));
+    let workers = option("--workers", 16);
+    let path = match args.first() {
+        Some(path) if !path.starts_with("--") => path.clone(),
+        _ => fail(String::from("missing tree")),
+    };
+    if port > u16::MAX as u64 {
+        fail(format!("port {} is out of range", port));
+    }
+    if workers == 0 {
+        fail(String::from("--workers must be at least 1"));
+    }
+
+    let tree = load(&path).unwrap_or_else(|e| {
+        eprintln!("arst-server: {}: {}", path, e);
+        process::exit(1);
+    });
+    let listener = TcpListener::bind(("127.0.0.1", port as u16)).unwrap_or_else(|e| {
+        eprintln!("arst-server: port {}: {}", port, e);
+        process::exit(1);
+    });
+    eprintln!("serving {} on http://127.0.0.1:{}", path, port);
+
+    let served = Arc::new(Served {
+        path,
+        tree: RwLock::new(Arc::new(tree)),
+    });
+    let reloading = served.clone();
+    thread::spawn(move || reload(&reloading, Duration::from_millis(interval)));
+
+    //a fixed set of threads takes connections off a channel with no room to spare, so once every
+    //worker is busy accepting stops and further clients wait in the listen backlog
+    let (connections, waiting) = mpsc::sync_channel::<TcpStream>(0);
+    let waiting = Arc::new(Mutex::new(waiting));
+    for _ in 0..workers {
+        let (served, waiting) = (served.clone(), waiting.clone());
+        thread::spawn(move || loop {
+            let stream = match waiting.lock().unwrap().recv() {
+                Ok(stream) => stream,
+                Err(_) => return,
+            };
+            if let Err(e) = handle(stream, &served) {
+                eprintln!("request failed: {}", e);
+            }
+        });
+    }
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => connections.send(stream).unwrap(),
+            Err(e) => eprintln!("accept failed: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode, json_string, read_request, respond, MAX_DISTANCE, MAX_HEADERS, MAX_LINE};
+    use fast_search::trie::enums::{Case, Match};
+    use fast_search::trie::tree::Tree;
+    use std::io;
+    use std::time::{Duration, Instant};
+
+    //status turning the request away, or the request line
+    fn read(request: &[u8], deadline: Instant) -> io::Result<Result<String, &'static str>> {
+        let unlimited = |_| Ok(());
+        Ok(read_request(&mut &request[..], deadline, &unlimited)?.map_err(|(status, _)| status))
+    }
+
+    #[test]
+    fn turns_away_oversized_requests() {
+        let later = Instant::now() + Duration::from_secs(60);
+        let request = b"GET /exists?q=a HTTP/1.1\r\nHost: localhost\r\n\r\n";
+        assert_eq!(read(request, later).unwrap(), Ok(String::from("GET /exists?q=a HTTP/1.1\r\n")));
+
+        let long_target = format!("GET /exists?q={} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE));
+        assert_eq!(read(long_target.as_bytes(), later).unwrap(), Err("400 Bad Request"));
+        let long_header = format!("GET / HTTP/1.1\r\nCookie: {}\r\n\r\n", "a".repeat(MAX_LINE));
+        assert_eq!(read(long_header.as_bytes(), later).unwrap(), Err("431 Request Header Fields Too Large"));
+        let many_headers = format!("GET / HTTP/1.1\r\n{}\r\n", "A: b\r\n".repeat(MAX_HEADERS));
+        assert_eq!(read(many_headers.as_bytes(), later).unwrap(), Err("431 Request Header Fields Too Large"));
+
+        let error = read(request, Instant::now()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn decodes_query_components() {
+        assert_eq!(decode("a+b%20c%C3%A9").as_deref(), Some("a b cé"));
+        assert_eq!(decode("%2"), None);
+        assert_eq!(decode("%zz"), None);
+        assert_eq!(decode("%ff"), None);
+    }
+
+    #[test]
+    fn escapes_json_strings() {
+        assert_eq!(json_string("a\"b\\c\n"), "\"a\\\"b\\\\c\\u000a\"");
+    }
+
+    #[test]
+    fn answers_queries() {
+        let mut tree = Tree::new(Match::Exact, Case::Sensitive, false);
+        for word in &["band", "banana", "bandana", "cat"] {
+            tree.add(word);
+        }
+
+        assert_eq!(respond(&tree, "/exists?q=cat"), ("200 OK", String::from("{\"exists\": true}")));
+        assert_eq!(
+            respond(&tree, "/complete?q=ban&limit=2"),
+            ("200 OK", String::from("{\"completions\": [\"banana\", \"band\"]}"))
+        );
+        assert_eq!(
+            respond(&tree, "/fuzzy?q=bat&distance=1"),
+            ("200 OK", String::from("{\"matches\": [\"cat\"]}"))
+        );
+        assert_eq!(respond(&tree, "/complete?q=b&limit=x").0, "400 Bad Request");
+        assert_eq!(respond(&tree, &format!("/fuzzy?q=bat&distance={}", MAX_DISTANCE)).0, "200 OK");
+        assert_eq!(
+            respond(&tree, &format!("/fuzzy?q=bat&distance={}", MAX_DISTANCE + 1)),
+            ("400 Bad Request", String::from("{\"error\": \"distance is at most 3\"}"))
+        );
+        assert_eq!(respond(&tree, "/exists").0, "400 Bad Request");
+        assert_eq!(respond(&tree, "/other?q=a").0, "404 Not Found");
+    }
+}
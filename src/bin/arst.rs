@@ -0,0 +1,405 @@
+use fast_search::trie::enums::{Allocation, Case, Match};
+use fast_search::trie::tree::Tree;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, IsTerminal, Write};
+use std::process;
+
+const USAGE: &str = "usage: arst <command> [options]
+
+commands:
+  build <words> <tree>              read one word per line into a saved tree
+        --case sensitive|insensitive    (default insensitive)
+        --match exact|prefix|prefix-postfix  (default exact)
+  query <tree> exists <word>        whether the word was added
+  query <tree> prefix <prefix>      whether any word starts with prefix
+  query <tree> complete <prefix>    words starting with prefix
+        --limit <n>                     (default 10)
+  query <tree> fuzzy <word>         words within a few character edits
+        --distance <n>                  (default 1)
+  stats <tree>                      node counts by type, depth and memory
+  dump <tree>                       every word in order
+  repl [tree]                       interactive session on a tree kept in memory
+        --case, --match                 as for build, when starting empty
+        --limit, --distance             as for query
+
+case and match are chosen at build time and saved with the tree";
+
+const REPL_HELP: &str = "add <word>      add a word
+rm <word>       remove a word
+?<word>         whether the word was added
+<prefix>*       words starting with prefix
+~<word>         words within a few character edits
+show <prefix>   every node on the way down to prefix
+quit";
+
+#[derive(Debug)]
+enum Error {
+    //bad command line, the usage is printed along with it
+    Usage(String),
+    //a file couldn't be read or written
+    File(String, io::Error),
+    //writing results failed
+    Output(io::Error),
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Output(e)
+    }
+}
+
+fn usage<T>(message: String) -> Result<T, Error> {
+    Err(Error::Usage(message))
+}
+
+//options given as --name value, anything else is positional
+struct Args {
+    positional: Vec<String>,
+    options: Vec<(String, String)>,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Args, Error> {
+        let mut parsed = Args {
+            positional: Vec::new(),
+            options: Vec::new(),
+        };
+        while let Some(arg) = args.next() {
+            if let Some(name) = arg.strip_prefix("--") {
+                match args.next() {
+                    Some(value) => parsed.options.push((String::from(name), value)),
+                    None => return usage(format!("--{} needs a value", name)),
+                }
+            } else {
+                parsed.positional.push(arg);
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(option, _)| option == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn number(&self, name: &str, default: usize) -> Result<usize, Error> {
+        match self.option(name) {
+            Some(value) => match value.parse() {
+                Ok(number) => Ok(number),
+                Err(_) => usage(format!("--{} expects a number, got {:?}", name, value)),
+            },
+            None => Ok(default),
+        }
+    }
+
+    //positionals after the command, exactly as many as the command takes
+    fn expect(&self, count: usize) -> Result<&[String], Error> {
+        if self.positional.len() == count + 1 {
+            Ok(&self.positional[1..])
+        } else {
+            usage(format!("{} takes {} arguments", self.positional[0], count))
+        }
+    }
+}
+
+fn case(value: Option<&str>) -> Result<Case, Error> {
+    match value.unwrap_or("insensitive") {
+        "sensitive" => Ok(Case::Sensitive),
+        "insensitive" => Ok(Case::Insensitve),
+        other => usage(format!("unknown case {:?}", other)),
+    }
+}
+
+fn matching(value: Option<&str>) -> Result<Match, Error> {
+    match value.unwrap_or("exact") {
+        "exact" => Ok(Match::Exact),
+        "prefix" => Ok(Match::Prefix),
+        "prefix-postfix" => Ok(Match::PrefixPostfix),
+        other => usage(format!("unknown match {:?}", other)),
+    }
+}
+
+fn load(path: &str) -> Result<Tree, Error> {
+    let file = File::open(path).map_err(|e| Error::File(String::from(path), e))?;
+    Tree::load(BufReader::new(file), Allocation::Arena).map_err(|e| Error::File(String::from(path), e))
+}
+
+fn build(args: &Args) -> Result<(), Error> {
+    let paths = args.expect(2)?;
+    let (case, matching) = (case(args.option("case"))?, matching(args.option("match"))?);
+    let words = File::open(&paths[0])
+        .and_then(|file| BufReader::new(file).lines().collect::<io::Result<Vec<_>>>())
+        .map_err(|e| Error::File(paths[0].clone(), e))?;
+
+    let words = words.iter().map(|word| word.trim());
+    let tree = Tree::par_build(matching, case, false, Allocation::Heap, words);
+    File::create(&paths[1])
+        .and_then(|file| tree.save(BufWriter::new(file)))
+        .map_err(|e| Error::File(paths[1].clone(), e))?;
+    eprintln!("{} words saved to {}", tree.stats().keys, paths[1]);
+    Ok(())
+}
+
+fn query(args: &Args, out: &mut impl Write) -> Result<(), Error> {
+    let positional = args.expect(3)?;
+    let (limit, distance) = (args.number("limit", 10)?, args.number("distance", 1)?);
+    let tree = load(&positional[0])?;
+
+    let value = &positional[2];
+    match positional[1].as_str() {
+        "exists" => writeln!(out, "{}", tree.exists(value))?,
+        "prefix" => writeln!(out, "{}", !tree.auto_complete(value, 1).is_empty())?,
+        "complete" => {
+            for word in tree.auto_complete(value, limit) {
+                writeln!(out, "{}", word)?;
+            }
+        }
+        "fuzzy" => {
+            for word in tree.fuzzy(value, distance) {
+                writeln!(out, "{}", word)?;
+            }
+        }
+        other => return usage(format!("unknown query {:?}", other)),
+    }
+    Ok(())
+}
+
+//node kind, child count and terminal flag from the root down to prefix
+fn show(tree: &Tree, prefix: &str, out: &mut impl Write) -> io::Result<()> {
+    for node in tree.path(prefix) {
+        let key = match node.key {
+            Some(key) => key.escape_ascii().to_string(),
+            None => String::from("(root)"),
+        };
+        let kind = format!("{:?}", node.kind);
+        let line = format!("{:<8}{:<9}size {:<5}{}", key, kind, node.size, if node.terminal { "terminal" } else { "" });
+        writeln!(out, "{}", line.trim_end())?;
+    }
+    //nodes that no longer lead to a word are removed, so the path ends early exactly when
+    //nothing starts with prefix
+    if !prefix.is_empty() && tree.auto_complete(prefix, 1).is_empty() {
+        writeln!(out, "no words start with {:?}", prefix)?;
+    }
+    Ok(())
+}
+
+//the prompt is only shown when reading from a terminal so piped sessions print just results
+fn repl(args: &Args, input: impl BufRead, prompt: bool, out: &mut impl Write) -> Result<(), Error> {
+    let (limit, distance) = (args.number("limit", 10)?, args.number("distance", 1)?);
+    let mut tree = match &args.positional[1..] {
+        [] => Tree::new(matching(args.option("match"))?, case(args.option("case"))?, false),
+        [path] => load(path)?,
+        _ => return usage(String::from("repl takes at most 1 argument")),
+    };
+
+    let prompt = if prompt { "> " } else { "" };
+    write!(out, "{}", prompt)?;
+    out.flush()?;
+    for line in input.lines() {
+        let line = line?;
+        let line = line.trim();
+        if let Some(word) = line.strip_prefix("add ") {
+            tree.add(word.trim());
+        } else if let Some(word) = line.strip_prefix("rm ") {
+            if !tree.remove(word.trim()) {
+                writeln!(out, "{:?} is not in the tree", word.trim())?;
+            }
+        } else if let Some(prefix) = line.strip_prefix("show ").or((line == "show").then_some("")) {
+            show(&tree, prefix.trim(), out)?;
+        } else if let Some(word) = line.strip_prefix('?') {
+            writeln!(out, "{}", tree.exists(word))?;
+        } else if let Some(word) = line.strip_prefix('~') {
+            for word in tree.fuzzy(word, distance) {
+                writeln!(out, "{}", word)?;
+            }
+        } else if let Some(prefix) = line.strip_suffix('*') {
+            for word in tree.auto_complete(prefix, limit) {
+                writeln!(out, "{}", word)?;
+            }
+        } else {
+            match line {
+                "" => {}
+                "help" => writeln!(out, "{}", REPL_HELP)?,
+                "quit" | "exit" => return Ok(()),
+                other => writeln!(out, "unknown command {:?}, try help", other)?,
+            }
+        }
+        write!(out, "{}", prompt)?;
+        out.flush()?;
+    }
+    //finish the line the last prompt was left on
+    if !prompt.is_empty() {
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+fn run(args: &Args, out: &mut impl Write) -> Result<(), Error> {
+    match args.positional.first().map(String::as_str) {
+        Some("build") => build(args)?,
+        Some("query") => query(args, out)?,
+        Some("stats") => writeln!(out, "{}", load(&args.expect(1)?[0])?.stats())?,
+        Some("dump") => {
+            for word in load(&args.expect(1)?[0])?.iter() {
+                writeln!(out, "{}", word)?;
+            }
+        }
+        Some("repl") => {
+            let stdin = io::stdin();
+            repl(args, stdin.lock(), stdin.is_terminal(), out)?
+        }
+        Some(other) => return usage(format!("unknown command {:?}", other)),
+        None => return usage(String::from("missing command")),
+    }
+    Ok(out.flush()?)
+}
+
+fn main() {
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    match Args::parse(env::args().skip(1)).and_then(|args| run(&args, &mut out)) {
+        Ok(()) => {}
+        Err(Error::Usage(message)) => {
+            eprintln!("arst: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+        Err(Error::File(path, e)) => {
+            eprintln!("arst: {}: {}", path, e);
+            process::exit(1);
+        }
+        //the reader went away, e.g. dump piped into head
+        Err(Error::Output(e)) if e.kind() == io::ErrorKind::BrokenPipe => {}
+        Err(Error::Output(e)) => {
+            eprintln!("arst: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{case, matching, repl, run, Args, Error};
+    use fast_search::trie::enums::{Case, Match};
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process;
+
+    fn args(line: &str) -> Result<Args, Error> {
+        Args::parse(line.split_whitespace().map(String::from))
+    }
+
+    //output of the command, or the error it stopped with
+    fn output(line: &str) -> Result<String, Error> {
+        let mut out = Vec::new();
+        run(&args(line)?, &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    //file under the temp directory no other test or process uses
+    fn temp(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("arst-{}-{}", process::id(), name))
+    }
+
+    #[test]
+    fn parses_options_and_positionals() {
+        let parsed = args("query words.arst --limit 3 complete ap --limit 5").unwrap();
+        assert_eq!(parsed.positional, vec!["query", "words.arst", "complete", "ap"]);
+        //the last of a repeated option wins
+        assert_eq!(parsed.number("limit", 10).unwrap(), 5);
+        assert_eq!(parsed.number("distance", 1).unwrap(), 1);
+        assert_eq!(parsed.expect(3).unwrap(), &parsed.positional[1..]);
+
+        assert!(matches!(args("dump --limit"), Err(Error::Usage(_))));
+        assert!(matches!(args("dump --limit many").unwrap().number("limit", 10), Err(Error::Usage(_))));
+        assert!(matches!(args("dump a b").unwrap().expect(1), Err(Error::Usage(_))));
+        assert!(matches!(output(""), Err(Error::Usage(_))));
+        assert!(matches!(output("grow words"), Err(Error::Usage(_))));
+    }
+
+    #[test]
+    fn maps_case_and_match_names() {
+        assert!(matches!(case(None), Ok(Case::Insensitve)));
+        assert!(matches!(case(Some("insensitive")), Ok(Case::Insensitve)));
+        assert!(matches!(case(Some("sensitive")), Ok(Case::Sensitive)));
+        assert!(matches!(case(Some("upper")), Err(Error::Usage(_))));
+
+        assert!(matches!(matching(None), Ok(Match::Exact)));
+        assert!(matches!(matching(Some("exact")), Ok(Match::Exact)));
+        assert!(matches!(matching(Some("prefix")), Ok(Match::Prefix)));
+        assert!(matches!(matching(Some("prefix-postfix")), Ok(Match::PrefixPostfix)));
+        assert!(matches!(matching(Some("suffix")), Err(Error::Usage(_))));
+    }
+
+    #[test]
+    fn builds_a_tree_that_queries_and_dumps() {
+        let (words, saved) = (temp("words.txt"), temp("words.arst"));
+        fs::write(&words, "Apple\napricot \nbanana\n").unwrap();
+        let (words, saved) = (words.to_str().unwrap(), saved.to_str().unwrap());
+
+        output(&format!("build {} {}", words, saved)).unwrap();
+        assert_eq!(output(&format!("dump {}", saved)).unwrap(), "apple\napricot\nbanana\n");
+        assert_eq!(output(&format!("query {} exists APPLE", saved)).unwrap(), "true\n");
+        assert_eq!(output(&format!("query {} prefix c", saved)).unwrap(), "false\n");
+        assert_eq!(output(&format!("query {} complete ap --limit 1", saved)).unwrap(), "apple\n");
+        assert_eq!(output(&format!("query {} fuzzy bandana", saved)).unwrap(), "banana\n");
+        assert!(matches!(output(&format!("query {} sort ap", saved)), Err(Error::Usage(_))));
+
+        //case is saved with the tree
+        output(&format!("build {} {} --case sensitive", words, saved)).unwrap();
+        assert_eq!(output(&format!("query {} exists apple", saved)).unwrap(), "false\n");
+        assert_eq!(output(&format!("query {} exists Apple", saved)).unwrap(), "true\n");
+
+        fs::remove_file(words).unwrap();
+        fs::remove_file(saved).unwrap();
+        assert!(matches!(output(&format!("dump {}", saved)), Err(Error::File(..))));
+    }
+
+    #[test]
+    fn repl_runs_a_script() {
+        let script = [
+            "add apple", "add apply", "add ape", "add app",
+            "?apple", "?ap", "app*", "~aple",
+            "rm ape", "rm ape", "?ape",
+            "show apple", "show zz", "show",
+            "fly", "quit", "add never",
+        ];
+        let mut out = Vec::new();
+        repl(&args("repl").unwrap(), script.join("\n").as_bytes(), false, &mut out).unwrap();
+
+        let expected = [
+            "true",
+            "false",
+            "app",
+            "apple",
+            "apply",
+            "ape",
+            "apple",
+            "\"ape\" is not in the tree",
+            "false",
+            //a is left with only p below it once ape is gone, app holds a value, appl branches
+            //to e and y and the leaf for apple holds nothing
+            "(root)  Node4    size 1",
+            "a       Node4    size 1",
+            "p       Node4    size 1",
+            "p       Node4    size 1    terminal",
+            "l       Node4    size 2",
+            "e       Node0    size 0    terminal",
+            "(root)  Node4    size 1",
+            "no words start with \"zz\"",
+            "(root)  Node4    size 1",
+            "unknown command \"fly\", try help",
+        ];
+        assert_eq!(String::from_utf8(out).unwrap(), expected.join("\n") + "\n");
+
+        //prompts only when asked to, ending on a fresh line
+        let mut out = Vec::new();
+        repl(&args("repl --case sensitive").unwrap(), "add A\n?a\n".as_bytes(), true, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "> > false\n> \n");
+        assert!(matches!(repl(&args("repl a b").unwrap(), "".as_bytes(), false, &mut Vec::new()), Err(Error::Usage(_))));
+    }
+}
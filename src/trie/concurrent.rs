@@ -0,0 +1,485 @@
+use crate::trie::enums::{Case, Synchronization};
+use crate::trie::nodes::versioned::{Restart, VersionedNode};
+use crossbeam_epoch::{self as epoch, Guard};
+
+//tree shared between threads
+//https://db.in.tum.de/~leis/papers/artsync.pdf
+//with optimistic lock coupling readers never write to shared memory, they validate node versions
+//and start over on conflict
+//with ROWEX readers don't validate anything, writers lock each node they change and publish
+//children before the keys leading to them
+//either way writers only lock the node they change, plus its parent when the node is replaced by a larger one
+//or unlinked once a remove leaves it empty
+pub struct ConcurrentTree {
+    case: Case,
+    synchronization: Synchronization,
+    //fixed Node256 so the root never grows and nothing has to lock above it
+    root: Box<VersionedNode>,
+}
+
+impl ConcurrentTree {
+    pub fn new(case: Case) -> Self {
+        ConcurrentTree::with_synchronization(case, Synchronization::OptimisticLockCoupling)
+    }
+
+    pub fn with_synchronization(case: Case, synchronization: Synchronization) -> Self {
+        ConcurrentTree {
+            case,
+            synchronization,
+            root: VersionedNode::node256(),
+        }
+    }
+
+    pub fn add(&self, value: &str) {
+        if !value.is_empty() {
+            let case_corrected = self.case.corrected(value);
+
+            let guard = epoch::pin();
+            let values = case_corrected.as_bytes();
+            loop {
+                let added = match self.synchronization {
+                    Synchronization::OptimisticLockCoupling => self.try_add(values, &guard),
+                    Synchronization::Rowex => self.try_add_rowex(values, &guard),
+                };
+                if added.is_ok() {
+                    break;
+                }
+            }
+        }
+    }
+
+    //returns whether the value was in the tree
+    //nodes left with neither children nor a value are unlinked bottom up and freed once no reader can be inside them
+    pub fn remove(&self, value: &str) -> bool {
+        let case_corrected = self.case.corrected(value);
+
+        let guard = epoch::pin();
+        loop {
+            if let Ok(removed) = self.try_remove(case_corrected.as_bytes(), &guard) {
+                return removed;
+            }
+        }
+    }
+
+    pub fn exists(&self, value: &str) -> bool {
+        let case_corrected = self.case.corrected(value);
+
+        let _guard = epoch::pin();
+        loop {
+            if let Ok(found) = self.try_exists(case_corrected.as_bytes()) {
+                return found;
+            }
+        }
+    }
+
+    //every value in order
+    pub fn iter(&self) -> impl Iterator<Item = String> {
+        self.auto_complete("", usize::MAX).into_iter()
+    }
+
+    //up to results values starting with value, in order
+    pub fn auto_complete(&self, value: &str, results: usize) -> Vec<String> {
+        let case_corrected = self.case.corrected(value);
+
+        let _guard = epoch::pin();
+        loop {
+            let mut found = Vec::new();
+            if self.try_complete(case_corrected.as_bytes(), results, &mut found).is_ok() {
+                return found
+                    .into_iter()
+                    .map(|value| String::from_utf8(value).unwrap())
+                    .collect();
+            }
+        }
+    }
+
+    //ROWEX readers ignore versions, they neither wait for writers nor restart
+    fn read_lock(&self, node: &VersionedNode) -> Result<u64, Restart> {
+        match self.synchronization {
+            Synchronization::OptimisticLockCoupling => node.read_lock(),
+            Synchronization::Rowex => Ok(0),
+        }
+    }
+
+    fn check(&self, node: &VersionedNode, version: u64) -> Result<(), Restart> {
+        match self.synchronization {
+            Synchronization::OptimisticLockCoupling => node.check(version),
+            Synchronization::Rowex => Ok(()),
+        }
+    }
+
+    fn try_exists(&self, values: &[u8]) -> Result<bool, Restart> {
+        let mut node: &VersionedNode = &self.root;
+        let mut version = self.read_lock(node)?;
+        for value in values {
+            let child = node.find_child(*value);
+            self.check(node, version)?;
+            if child.is_null() {
+                return Ok(false);
+            }
+            node = unsafe { &*child };
+            version = self.read_lock(node)?;
+        }
+        let terminal = node.is_terminal();
+        self.check(node, version)?;
+        Ok(terminal)
+    }
+
+    fn try_complete(
+        &self,
+        values: &[u8],
+        results: usize,
+        found: &mut Vec<Vec<u8>>,
+    ) -> Result<(), Restart> {
+        let mut node: &VersionedNode = &self.root;
+        let mut version = self.read_lock(node)?;
+        for value in values {
+            let child = node.find_child(*value);
+            self.check(node, version)?;
+            if child.is_null() {
+                return Ok(());
+            }
+            node = unsafe { &*child };
+            version = self.read_lock(node)?;
+        }
+
+        //depth first with children pushed in reverse so values come out in order
+        let mut pending = vec![(node, version, values.to_vec())];
+        while let Some((node, version, key)) = pending.pop() {
+            let terminal = node.is_terminal();
+            let entries = node.entries();
+            self.check(node, version)?;
+            if terminal {
+                found.push(key.clone());
+                if found.len() == results {
+                    return Ok(());
+                }
+            }
+            for (value, child) in entries.into_iter().rev() {
+                let child = unsafe { &*child };
+                let mut key = key.clone();
+                key.push(value);
+                pending.push((child, self.read_lock(child)?, key));
+            }
+        }
+        Ok(())
+    }
+
+    fn try_add(&self, values: &[u8], guard: &Guard) -> Result<(), Restart> {
+        let mut parent: Option<(&VersionedNode, u64)> = None;
+        let mut node: &VersionedNode = &self.root;
+        let mut version = node.read_lock()?;
+        for (depth, value) in values.iter().enumerate() {
+            let child = node.find_child(*value);
+            node.check(version)?;
+            if !child.is_null() {
+                if let Some((parent, parent_version)) = parent {
+                    parent.check(parent_version)?;
+                }
+                parent = Some((node, version));
+                node = unsafe { &*child };
+                version = node.read_lock()?;
+                continue;
+            }
+
+            if !node.has_room(*value) {
+                let (parent, parent_version) = parent.expect("the root never fills up");
+                parent.upgrade_lock(parent_version)?;
+                if let Err(restart) = node.upgrade_lock(version) {
+                    parent.unlock();
+                    return Err(restart);
+                }
+                self.grow(parent, node, &values[depth - 1..], guard);
+            } else {
+                node.upgrade_lock(version)?;
+                node.insert_child(*value, VersionedNode::chain(&values[depth + 1..]));
+                node.unlock();
+            }
+            return Ok(());
+        }
+
+        node.upgrade_lock(version)?;
+        node.set_terminal(true);
+        node.unlock();
+        Ok(())
+    }
+
+    fn try_add_rowex(&self, values: &[u8], guard: &Guard) -> Result<(), Restart> {
+        let mut parent: Option<&VersionedNode> = None;
+        let mut node: &VersionedNode = &self.root;
+        for (depth, value) in values.iter().enumerate() {
+            let child = node.find_child(*value);
+            if !child.is_null() {
+                parent = Some(node);
+                node = unsafe { &*child };
+                continue;
+            }
+
+            node.lock()?;
+            //another writer may have added the same key while this one waited
+            if !node.find_child(*value).is_null() {
+                node.unlock();
+                return Err(Restart);
+            }
+            if !node.has_room(*value) {
+                //every writer locks the child before the parent so they can't deadlock
+                //the parent still points at the node since only the node's lock holder replaces it
+                let parent = parent.expect("the root never fills up");
+                if let Err(restart) = parent.lock() {
+                    node.unlock();
+                    return Err(restart);
+                }
+                self.grow(parent, node, &values[depth - 1..], guard);
+            } else {
+                node.insert_child(*value, VersionedNode::chain(&values[depth + 1..]));
+                node.unlock();
+            }
+            return Ok(());
+        }
+
+        node.lock()?;
+        node.set_terminal(true);
+        node.unlock();
+        Ok(())
+    }
+
+    //the same under both schemes, writers in either one publish children before the keys leading to them
+    //so the way down needs no validation, locking the node fails instead if it was replaced meanwhile
+    fn try_remove(&self, values: &[u8], guard: &Guard) -> Result<bool, Restart> {
+        let mut node: &VersionedNode = &self.root;
+        for value in values {
+            let child = node.find_child(*value);
+            if child.is_null() {
+                return Ok(false);
+            }
+            node = unsafe { &*child };
+        }
+
+        node.lock()?;
+        if !node.is_terminal() {
+            node.unlock();
+            return Ok(false);
+        }
+        node.set_terminal(false);
+
+        //the locked node can't gain a child or value, whoever tries has to lock it first
+        //and finds it obsolete once it is unlinked
+        let mut depth = values.len();
+        while depth > 0 && !node.is_terminal() && node.entries().is_empty() {
+            let parent = self.lock_parent(&values[..depth]);
+            parent.remove_child(values[depth - 1]);
+            node.unlock_obsolete();
+            let unlinked = node as *const VersionedNode as *mut VersionedNode;
+            unsafe { guard.defer_unchecked(move || drop(Box::from_raw(unlinked))) };
+            node = parent;
+            depth -= 1;
+        }
+        node.unlock();
+        Ok(true)
+    }
+
+    //write locks the node leading to the node at values, which the caller holds locked
+    //a locked node stays reachable, so if the parent found was replaced by a larger copy the
+    //way down is taken again to find the copy
+    //locks are taken child first like growing does, so writers never wait on each other in a cycle
+    fn lock_parent(&self, values: &[u8]) -> &VersionedNode {
+        loop {
+            let mut parent: &VersionedNode = &self.root;
+            for value in &values[..values.len() - 1] {
+                parent = unsafe { &*parent.find_child(*value) };
+            }
+            if parent.lock().is_ok() {
+                return parent;
+            }
+        }
+    }
+
+    //swap the full node for a larger copy, both it and its parent are write locked
+    //values starts with the parent's key for the node followed by the key that didn't fit
+    fn grow(&self, parent: &VersionedNode, node: &VersionedNode, values: &[u8], guard: &Guard) {
+        let grown = node.grow();
+        grown.insert_child(values[1], VersionedNode::chain(&values[2..]));
+        parent.replace_child(values[0], Box::into_raw(grown));
+        node.unlock_obsolete();
+        parent.unlock();
+
+        //readers that got in before the swap may still be inside the old node
+        let node = node as *const VersionedNode as *mut VersionedNode;
+        unsafe { guard.defer_unchecked(move || drop(Box::from_raw(node))) };
+    }
+}
+
+impl Drop for ConcurrentTree {
+    fn drop(&mut self) {
+        //replaced nodes are no longer reachable, the epoch collector frees those
+        let mut reachable = self.root.entries();
+        while let Some((_, node)) = reachable.pop() {
+            let node = unsafe { Box::from_raw(node) };
+            reachable.extend(node.entries());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Case, ConcurrentTree, Synchronization};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+
+    const MODES: [Synchronization; 2] =
+        [Synchronization::OptimisticLockCoupling, Synchronization::Rowex];
+
+    fn tree(case: Case, synchronization: Synchronization) -> ConcurrentTree {
+        ConcurrentTree::with_synchronization(case, synchronization)
+    }
+
+    #[test]
+    fn exists_only_for_added_words() {
+        for synchronization in MODES {
+            let tree = tree(Case::Insensitve, synchronization);
+            for word in &["a", "ab", "abc", "abd", "b", "bcd"] {
+                tree.add(word);
+            }
+
+            for word in &["a", "AB", "abc", "abd", "b", "bcd"] {
+                assert!(tree.exists(word));
+            }
+            for word in &["", "abcd", "bc", "c"] {
+                assert!(!tree.exists(word));
+            }
+            assert_eq!(tree.auto_complete("ab", 10), vec!["ab", "abc", "abd"]);
+            assert_eq!(tree.auto_complete("a", 2), vec!["a", "ab"]);
+            assert_eq!(
+                tree.iter().collect::<Vec<_>>(),
+                vec!["a", "ab", "abc", "abd", "b", "bcd"]
+            );
+        }
+    }
+
+    #[test]
+    fn remove_unlinks_emptied_nodes() {
+        for synchronization in MODES {
+            let tree = tree(Case::Insensitve, synchronization);
+            for word in &["a", "abc", "abd", "b"] {
+                tree.add(word);
+            }
+
+            assert!(!tree.remove("ab"));
+            assert!(tree.remove("ABC"));
+            assert!(!tree.remove("abc"));
+            assert_eq!(tree.iter().collect::<Vec<_>>(), vec!["a", "abd", "b"]);
+            //the b node stays for abd, a stays as a value
+            assert!(tree.remove("abd"));
+            assert_eq!(tree.auto_complete("a", 10), vec!["a"]);
+            assert!(tree.root.entries().iter().all(|(_, node)| unsafe { &**node }.is_terminal()));
+
+            //slots of removed children are taken again rather than growing the node
+            for key in 'c'..='f' {
+                tree.add(&format!("a{}", key));
+            }
+            assert!(tree.remove("ad"));
+            tree.add("ag");
+            assert_eq!(tree.auto_complete("a", 10), vec!["a", "ac", "ae", "af", "ag"]);
+
+            for word in &["a", "ac", "ae", "af", "ag", "b"] {
+                assert!(tree.remove(word));
+            }
+            assert!(tree.root.entries().is_empty());
+            assert_eq!(tree.iter().count(), 0);
+        }
+    }
+
+    //every writer adds under the same prefixes so nodes grow up to Node256 while others use them
+    fn word(writer: usize, i: usize) -> String {
+        let first = (b'a' + (i % 7) as u8) as char;
+        let second = (b'0' + (i / 7 % 64) as u8) as char;
+        format!("{}{}{}", first, second, i * 4 + writer)
+    }
+
+    //each writer removes every third word it added LAG steps earlier, so removes unlink nodes
+    //and empty slots while other writers add below them and readers walk through them
+    const LAG: usize = 10;
+
+    fn removed(i: usize, done: usize) -> bool {
+        i.is_multiple_of(3) && i + LAG < done
+    }
+
+    #[test]
+    fn readers_see_every_completed_add_and_remove() {
+        const WRITERS: usize = 4;
+        const WORDS: usize = 2000;
+        for synchronization in MODES {
+            let tree = tree(Case::Sensitive, synchronization);
+            //number of words each writer has finished adding
+            let progress = (0..WRITERS).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>();
+            let snapshot = || {
+                progress
+                    .iter()
+                    .map(|done| done.load(Ordering::Acquire))
+                    .collect::<Vec<_>>()
+            };
+            let finished = || snapshot().iter().all(|&done| done == WORDS);
+
+            thread::scope(|scope| {
+                for writer in 0..WRITERS {
+                    let (tree, progress) = (&tree, &progress);
+                    scope.spawn(move || {
+                        for i in 0..WORDS {
+                            tree.add(&word(writer, i));
+                            if i >= LAG && (i - LAG).is_multiple_of(3) {
+                                assert!(tree.remove(&word(writer, i - LAG)));
+                            }
+                            progress[writer].store(i + 1, Ordering::Release);
+                        }
+                    });
+                }
+
+                scope.spawn(|| {
+                    while !finished() {
+                        for (writer, done) in snapshot().into_iter().enumerate() {
+                            //words near done may be removed by the time they are looked up
+                            for i in (0..done).step_by(97) {
+                                if !i.is_multiple_of(3) {
+                                    assert!(tree.exists(&word(writer, i)));
+                                } else if removed(i, done) {
+                                    assert!(!tree.exists(&word(writer, i)));
+                                }
+                            }
+                            assert!(!tree.exists(&format!("{}z", word(writer, done))));
+                        }
+                    }
+                });
+
+                scope.spawn(|| {
+                    while !finished() {
+                        let done = snapshot();
+                        let all = tree.iter().collect::<Vec<_>>();
+                        assert!(all.windows(2).all(|pair| pair[0] < pair[1]));
+                        for (writer, done) in done.into_iter().enumerate() {
+                            for i in (0..done).step_by(31) {
+                                if !i.is_multiple_of(3) {
+                                    assert!(all.binary_search(&word(writer, i)).is_ok());
+                                } else if removed(i, done) {
+                                    assert!(all.binary_search(&word(writer, i)).is_err());
+                                }
+                            }
+                        }
+
+                        let completed = tree.auto_complete("c", 50);
+                        assert!(completed.len() <= 50);
+                        assert!(completed.iter().all(|value| value.starts_with('c')));
+                        assert!(completed.windows(2).all(|pair| pair[0] < pair[1]));
+                    }
+                });
+            });
+
+            let kept = (0..WORDS).filter(|&i| !removed(i, WORDS)).count();
+            assert_eq!(tree.iter().count(), WRITERS * kept);
+            for writer in 0..WRITERS {
+                for i in 0..WORDS {
+                    assert_eq!(tree.exists(&word(writer, i)), !removed(i, WORDS));
+                }
+            }
+        }
+    }
+}
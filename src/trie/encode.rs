@@ -0,0 +1,262 @@
+use std::convert::TryInto;
+
+//order preserving byte encodings, comparing encoded bytes gives the same order as comparing values
+//fixed width values are written big-endian so they need no separator, variable length values
+//escape 0x00 as 0x00 0xff and end with 0x00 0x01, which sorts below anything that could
+//continue the value, so shorter values come first and tuples can concatenate their fields
+pub trait KeyEncode: Sized {
+    fn encode(&self, out: &mut Vec<u8>);
+    //value at the front of input and whatever follows it
+    fn decode(input: &[u8]) -> Option<(Self, &[u8])>;
+
+    fn to_key(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode(&mut out);
+        out
+    }
+
+    //the whole of input has to be one value
+    fn from_key(input: &[u8]) -> Option<Self> {
+        match Self::decode(input)? {
+            (value, []) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+fn split<const N: usize>(input: &[u8]) -> Option<([u8; N], &[u8])> {
+    if input.len() < N {
+        return None;
+    }
+    let (bytes, rest) = input.split_at(N);
+    Some((bytes.try_into().ok()?, rest))
+}
+
+macro_rules! unsigned {
+    ($($t:ty),*) => {$(
+        impl KeyEncode for $t {
+            fn encode(&self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_be_bytes());
+            }
+
+            fn decode(input: &[u8]) -> Option<(Self, &[u8])> {
+                let (bytes, rest) = split(input)?;
+                Some((<$t>::from_be_bytes(bytes), rest))
+            }
+        }
+    )*};
+}
+
+unsigned!(u8, u16, u32, u64, u128);
+
+//flipping the sign bit moves negative numbers below positive ones
+macro_rules! signed {
+    ($($t:ty => $u:ty),*) => {$(
+        impl KeyEncode for $t {
+            fn encode(&self, out: &mut Vec<u8>) {
+                ((*self as $u) ^ (1 << (<$u>::BITS - 1))).encode(out)
+            }
+
+            fn decode(input: &[u8]) -> Option<(Self, &[u8])> {
+                let (bits, rest) = <$u>::decode(input)?;
+                Some(((bits ^ (1 << (<$u>::BITS - 1))) as $t, rest))
+            }
+        }
+    )*};
+}
+
+signed!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128);
+
+//positive floats only need the sign bit set to sort above negative ones, negative floats
+//also need the rest flipped so larger magnitudes sort lower
+//the order is the one total_cmp gives, -0.0 before 0.0 and NaNs at either end
+macro_rules! float {
+    ($($t:ty => $u:ty),*) => {$(
+        impl KeyEncode for $t {
+            fn encode(&self, out: &mut Vec<u8>) {
+                let bits = self.to_bits();
+                let sign = 1 << (<$u>::BITS - 1);
+                let bits = if bits & sign == 0 { bits | sign } else { !bits };
+                bits.encode(out)
+            }
+
+            fn decode(input: &[u8]) -> Option<(Self, &[u8])> {
+                let (bits, rest) = <$u>::decode(input)?;
+                let sign = 1 << (<$u>::BITS - 1);
+                let bits = if bits & sign != 0 { bits & !sign } else { !bits };
+                Some((<$t>::from_bits(bits), rest))
+            }
+        }
+    )*};
+}
+
+float!(f32 => u32, f64 => u64);
+
+impl KeyEncode for bool {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8)
+    }
+
+    fn decode(input: &[u8]) -> Option<(Self, &[u8])> {
+        match u8::decode(input)? {
+            (0, rest) => Some((false, rest)),
+            (1, rest) => Some((true, rest)),
+            _ => None,
+        }
+    }
+}
+
+//fixed size byte strings such as uuids, written as they are
+impl<const N: usize> KeyEncode for [u8; N] {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(self)
+    }
+
+    fn decode(input: &[u8]) -> Option<(Self, &[u8])> {
+        split(input)
+    }
+}
+
+impl KeyEncode for Vec<u8> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        for &byte in self {
+            out.push(byte);
+            if byte == 0 {
+                out.push(0xff);
+            }
+        }
+        out.extend_from_slice(&[0, 1]);
+    }
+
+    fn decode(mut input: &[u8]) -> Option<(Self, &[u8])> {
+        let mut value = Vec::new();
+        loop {
+            match *input {
+                [0, 0xff, ref rest @ ..] => {
+                    value.push(0);
+                    input = rest;
+                }
+                [0, 1, ref rest @ ..] => return Some((value, rest)),
+                [0, ..] => return None,
+                [byte, ref rest @ ..] => {
+                    value.push(byte);
+                    input = rest;
+                }
+                [] => return None,
+            }
+        }
+    }
+}
+
+impl KeyEncode for String {
+    fn encode(&self, out: &mut Vec<u8>) {
+        //same bytes as the Vec<u8> encoding without copying
+        for &byte in self.as_bytes() {
+            out.push(byte);
+            if byte == 0 {
+                out.push(0xff);
+            }
+        }
+        out.extend_from_slice(&[0, 1]);
+    }
+
+    fn decode(input: &[u8]) -> Option<(Self, &[u8])> {
+        let (bytes, rest) = Vec::<u8>::decode(input)?;
+        Some((String::from_utf8(bytes).ok()?, rest))
+    }
+}
+
+//fields one after another, ordered by the first field then the next
+macro_rules! tuple {
+    ($($name:ident),*) => {
+        impl<$($name: KeyEncode),*> KeyEncode for ($($name,)*) {
+            #[allow(non_snake_case)]
+            fn encode(&self, out: &mut Vec<u8>) {
+                let ($($name,)*) = self;
+                $($name.encode(out);)*
+            }
+
+            #[allow(non_snake_case)]
+            fn decode(input: &[u8]) -> Option<(Self, &[u8])> {
+                $(let ($name, input) = $name::decode(input)?;)*
+                Some((($($name,)*), input))
+            }
+        }
+    };
+}
+
+tuple!(A, B);
+tuple!(A, B, C);
+tuple!(A, B, C, D);
+tuple!(A, B, C, D, E);
+
+#[cfg(test)]
+mod tests {
+    use super::KeyEncode;
+    use proptest::prelude::*;
+    use std::cmp::Ordering;
+    use std::fmt::Debug;
+
+    //encoded order matches the values' order and every key decodes back to its value
+    fn preserves<T: KeyEncode + Debug>(a: T, b: T, order: Ordering) -> Result<(), TestCaseError> {
+        let (a_key, b_key) = (a.to_key(), b.to_key());
+        prop_assert_eq!(a_key.cmp(&b_key), order, "{:?} {:?}", a, b);
+        prop_assert_eq!(T::from_key(&a_key).map(|a| a.to_key()), Some(a_key));
+        prop_assert_eq!(T::from_key(&b_key).map(|b| b.to_key()), Some(b_key));
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn integers_keep_their_order(a: i64, b: i64, c: u32, d: u32, e: i8, f: i8) {
+            preserves(a, b, a.cmp(&b))?;
+            preserves(c, d, c.cmp(&d))?;
+            preserves(e, f, e.cmp(&f))?;
+        }
+
+        #[test]
+        fn floats_keep_their_total_order(a: f64, b: f64, c: f32, d: f32) {
+            preserves(a, b, a.total_cmp(&b))?;
+            preserves(c, d, c.total_cmp(&d))?;
+        }
+
+        #[test]
+        fn strings_keep_their_order_with_nul_bytes(
+            a in prop::collection::vec(prop::sample::select(vec![0u8, 1, 0xff, b'a']), 0..6),
+            b in prop::collection::vec(prop::sample::select(vec![0u8, 1, 0xff, b'a']), 0..6),
+        ) {
+            preserves(a.clone(), b.clone(), a.cmp(&b))?;
+        }
+
+        #[test]
+        fn tuples_order_by_field(
+            a in (any::<u32>(), "[a\0b]{0,3}", any::<i64>()),
+            b in (any::<u32>(), "[a\0b]{0,3}", any::<i64>()),
+        ) {
+            preserves(a.clone(), b.clone(), a.cmp(&b))?;
+        }
+    }
+
+    #[test]
+    fn float_edges_sort_like_total_cmp() {
+        let values = [f64::NEG_INFINITY, -1.5, -0.0, 0.0, f64::MIN_POSITIVE, 2.0, f64::INFINITY, f64::NAN];
+        for pair in values.windows(2) {
+            assert!(pair[0].to_key() < pair[1].to_key(), "{} {}", pair[0], pair[1]);
+        }
+        assert!(f64::from_key(&f64::NAN.to_key()).unwrap().is_nan());
+    }
+
+    #[test]
+    fn rejects_malformed_keys() {
+        assert_eq!(u32::from_key(&[1, 2, 3]), None);
+        assert_eq!(u8::from_key(&[1, 2]), None);
+        assert_eq!(String::from_key(b"abc"), None);
+        assert_eq!(String::from_key(b"a\0"), None);
+        assert_eq!(String::from_key(b"\xff\0\x01"), None);
+        assert_eq!(bool::from_key(&[2]), None);
+        assert_eq!(<(String, u8)>::from_key(b"a\0\xff\0\x01\x07"), Some((String::from("a\0"), 7)));
+        //a field after a string can start with the byte used for escaping
+        assert_eq!(<(String, u8)>::from_key(&(String::from("a"), 0xffu8).to_key()), Some((String::from("a"), 0xff)));
+        assert_eq!(<[u8; 2]>::from_key(&[1, 2]), Some([1, 2]));
+    }
+}
@@ -1,15 +1,58 @@
+use std::borrow::Cow;
+
+#[derive(Clone, Copy)]
 pub enum Match {
     Exact,
     Prefix,
     PrefixPostfix,
 }
 
+#[derive(Clone, Copy)]
 pub enum Case {
     Sensitive,
     Insensitve,
 }
 
-enum LinkId {
-    End,
-    Char(char),
+impl Case {
+    //value as trees store it, only case insensitive trees allocate a lowercased copy
+    pub(crate) fn corrected(self, value: &str) -> Cow<'_, str> {
+        match self {
+            Case::Insensitve => Cow::Owned(value.to_lowercase()),
+            Case::Sensitive => Cow::Borrowed(value),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Allocation {
+    Heap,
+    Arena,
+}
+
+//node types of the adaptive tree, named after how many children they hold
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    Node0,
+    Node4,
+    Node16,
+    Node48,
+    Node256,
+}
+
+//how a ConcurrentTree keeps readers and writers apart
+#[derive(Clone, Copy)]
+pub enum Synchronization {
+    //readers validate node versions and restart when a writer got in between
+    OptimisticLockCoupling,
+    //readers never lock or restart, writers keep every node consistent while changing it
+    Rowex,
+}
+
+//which occurrences Tree::find_all_in reports when values overlap in the text
+#[derive(Clone, Copy)]
+pub enum Occurrences {
+    //every occurrence of every value, "he" and "she" both inside "ushers"
+    Overlapping,
+    //scanning left to right the longest value starting first, then on from its end
+    LeftmostLongest,
 }
@@ -0,0 +1,98 @@
+use crate::trie::enums::NodeKind;
+use std::error::Error;
+use std::fmt;
+
+//returned when a bulk load is given values out of order
+#[derive(Debug, PartialEq)]
+pub struct UnsortedError {
+    pub position: usize, //index of the offending value in the input
+    pub value: String,
+}
+
+impl fmt::Display for UnsortedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} at position {} sorts before the value preceding it", self.value, self.position)
+    }
+}
+
+impl Error for UnsortedError {}
+
+//returned when an address block can't be parsed or is longer than its address
+#[derive(Debug, PartialEq)]
+pub struct PrefixError {
+    pub prefix: String,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for PrefixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid prefix {:?}, {}", self.prefix, self.reason)
+    }
+}
+
+impl Error for PrefixError {}
+
+//returned when a snapshot is asked of an arena tree, its nodes live in the arena and can't be
+//shared with the snapshot the way heap nodes are
+#[derive(Debug, PartialEq)]
+pub struct SnapshotError;
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "arena trees can't be snapshot without copying them, use Allocation::Heap")
+    }
+}
+
+impl Error for SnapshotError {}
+
+//node that breaks one of the invariants the node types rely on, see Tree::validate
+#[derive(Debug, PartialEq)]
+pub struct InvalidNode {
+    pub path: Vec<u8>, //bytes leading to the node from the root
+    pub kind: NodeKind,
+    pub violation: Violation,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Violation {
+    //size doesn't match the number of children held
+    Size { size: usize, children: usize },
+    //slot at or past size holds a child, or one before it is empty
+    Slot { slot: usize },
+    //Node4 slot with a key and no child or the other way round
+    KeyWithoutChild { slot: usize },
+    //Node4 key held in more than one slot
+    Duplicate { key: u8 },
+    //Node16 key not greater than the one before it
+    Unsorted { slot: usize },
+    //Node48 key pointing past the children in use
+    IndexOutOfRange { key: u8, index: u8 },
+    //Node48 key pointing at a child another key already points at
+    DuplicateIndex { key: u8, index: u8 },
+    //Node48 child no key points at
+    Unreferenced { slot: usize },
+    //looking the key up doesn't lead to the child listed under it
+    Misplaced { key: u8 },
+    //node below the root with no children that isn't terminal
+    EmptyLeaf,
+}
+
+impl fmt::Display for InvalidNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} at \"{}\": ", self.kind, self.path.escape_ascii())?;
+        match &self.violation {
+            Violation::Size { size, children } => write!(f, "size is {} but it holds {} children", size, children),
+            Violation::Slot { slot } => write!(f, "slot {} is out of step with size", slot),
+            Violation::KeyWithoutChild { slot } => write!(f, "slot {} has only one of key and child", slot),
+            Violation::Duplicate { key } => write!(f, "key {} is held more than once", key),
+            Violation::Unsorted { slot } => write!(f, "key in slot {} is not greater than the one before", slot),
+            Violation::IndexOutOfRange { key, index } => write!(f, "key {} points at unused slot {}", key, index),
+            Violation::DuplicateIndex { key, index } => write!(f, "key {} points at slot {} which is already taken", key, index),
+            Violation::Unreferenced { slot } => write!(f, "no key points at slot {}", slot),
+            Violation::Misplaced { key } => write!(f, "looking up key {} doesn't find its child", key),
+            Violation::EmptyLeaf => write!(f, "leaf is neither terminal nor the root"),
+        }
+    }
+}
+
+impl Error for InvalidNode {}
@@ -0,0 +1,55 @@
+use crate::trie::enums::{Case, Occurrences};
+use crate::trie::nodes::automaton::Automaton;
+use std::ops::Range;
+
+//a tree's values compiled for scanning text for them, see Tree::matcher
+//building it walks the whole tree once, keep it around to scan many texts against the same values
+//changes made to the tree afterwards don't reach it
+pub struct Matcher {
+    case: Case,
+    automaton: Automaton,
+}
+
+impl Matcher {
+    pub(crate) fn new(case: Case, automaton: Automaton) -> Self {
+        Matcher { case, automaton }
+    }
+
+    //byte range in text of each value occurring in it, ordered by start then end
+    //ranges are into text as given, even when lowercasing it changed the length of a character
+    pub fn find_all_in(&self, text: &str, occurrences: Occurrences) -> Vec<Range<usize>> {
+        match self.case {
+            Case::Sensitive => self.find_all_in_bytes(text.as_bytes(), occurrences),
+            Case::Insensitve => {
+                //the whole text is lowercased at once for the final sigma, the byte lengths
+                //still come out the same as lowercasing one character at a time
+                let lowered = text.to_lowercase();
+                let mut chars = Vec::with_capacity(lowered.len());
+                for (start, c) in text.char_indices() {
+                    let length = c.to_lowercase().map(char::len_utf8).sum::<usize>();
+                    chars.extend(std::iter::repeat_n(start..start + c.len_utf8(), length));
+                }
+                let mut found = self
+                    .find_all_in_bytes(lowered.as_bytes(), occurrences)
+                    .into_iter()
+                    .map(|found| chars[found.start].start..chars[found.end - 1].end)
+                    .collect::<Vec<_>>();
+                //values ending inside the same character land on the same range
+                found.dedup();
+                found
+            }
+        }
+    }
+
+    //like find_all_in without case correction
+    pub fn find_all_in_bytes(&self, text: &[u8], occurrences: Occurrences) -> Vec<Range<usize>> {
+        match occurrences {
+            Occurrences::Overlapping => {
+                let mut found = self.automaton.overlapping(text);
+                found.sort_unstable_by_key(|found| (found.start, found.end));
+                found
+            }
+            Occurrences::LeftmostLongest => self.automaton.leftmost_longest(text),
+        }
+    }
+}
@@ -1,4 +1,14 @@
-mod enums;
+pub mod concurrent;
+pub mod encode;
+pub mod enums;
+pub mod errors;
+pub mod matcher;
+pub mod persistent;
+pub mod routing;
+pub mod sharded;
+pub mod stats;
+pub(crate) mod substring;
 // mod node;
-mod nodes;
-mod tree;
+pub(crate) mod nodes;
+pub mod tree;
+pub mod typed;
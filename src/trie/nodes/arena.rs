@@ -0,0 +1,265 @@
+use crate::trie::nodes::node::{NodeMut, NodeRef};
+use crate::trie::nodes::store::Store;
+use crate::trie::nodes::{
+    node0::Node0, node16::Node16, node256::Node256, node4::Node4, node48::Node48,
+};
+use std::num::NonZeroU32;
+
+const KIND_SHIFT: u32 = 29;
+const INDEX_MASK: u32 = (1 << KIND_SHIFT) - 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    N0 = 1,
+    N4,
+    N16,
+    N48,
+    N256,
+}
+
+//compact link into an arena, the node kind is kept in the top bits and the slab index in the rest
+//kinds start at 1 so Option<NodeId> is still only four bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeId(NonZeroU32);
+
+impl NodeId {
+    fn new(kind: Kind, index: usize) -> Self {
+        assert!(index <= INDEX_MASK as usize, "arena slab is full");
+        NodeId(NonZeroU32::new((kind as u32) << KIND_SHIFT | index as u32).unwrap())
+    }
+
+    fn kind(self) -> Kind {
+        match self.0.get() >> KIND_SHIFT {
+            1 => Kind::N0,
+            2 => Kind::N4,
+            3 => Kind::N16,
+            4 => Kind::N48,
+            5 => Kind::N256,
+            _ => unreachable!(),
+        }
+    }
+
+    fn index(self) -> usize {
+        (self.0.get() & INDEX_MASK) as usize
+    }
+
+    //the same node after its slab has been appended behind offsets[kind] entries
+    fn shift(self, offsets: &[usize; 5]) -> Self {
+        let kind = self.kind();
+        NodeId::new(kind, self.index() + offsets[kind as usize - 1])
+    }
+}
+
+//nodes of a single type stored contiguously, freed slots are reused before the slab grows
+#[derive(Debug)]
+struct Slab<T> {
+    entries: Vec<T>,
+    free: Vec<u32>,
+}
+
+impl<T> Default for Slab<T> {
+    fn default() -> Self {
+        Slab {
+            entries: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+}
+
+impl<T> Slab<T> {
+    //move every entry of other to the end of this slab
+    fn append(&mut self, mut other: Slab<T>) {
+        let offset = self.entries.len() as u32;
+        self.free.extend(other.free.iter().map(|index| index + offset));
+        self.entries.append(&mut other.entries);
+    }
+
+    fn alloc(&mut self, node: T) -> usize {
+        if let Some(index) = self.free.pop() {
+            self.entries[index as usize] = node;
+            index as usize
+        } else {
+            self.entries.push(node);
+            self.entries.len() - 1
+        }
+    }
+
+    fn free(&mut self, index: usize) {
+        self.free.push(index as u32);
+    }
+}
+
+//nodes live in one slab per node type and refer to each other by NodeId
+//none of the slabs hold anything that needs dropping so the whole tree is freed
+//by releasing a handful of buffers instead of walking every node
+#[derive(Debug, Default)]
+pub struct Arena {
+    n0: Slab<Node0>,
+    n4: Slab<Node4<NodeId>>,
+    n16: Slab<Node16<NodeId>>,
+    n48: Slab<Node48<NodeId>>,
+    n256: Slab<Node256<NodeId>>,
+}
+
+impl Arena {
+    pub fn new() -> Self {
+        Arena::default()
+    }
+}
+
+impl Store for Arena {
+    type Link = NodeId;
+
+    #[inline]
+    fn get<'a>(&'a self, link: &'a NodeId) -> NodeRef<'a, NodeId> {
+        let index = link.index();
+        match link.kind() {
+            Kind::N0 => NodeRef::N0(&self.n0.entries[index]),
+            Kind::N4 => NodeRef::N4(&self.n4.entries[index]),
+            Kind::N16 => NodeRef::N16(&self.n16.entries[index]),
+            Kind::N48 => NodeRef::N48(&self.n48.entries[index]),
+            Kind::N256 => NodeRef::N256(&self.n256.entries[index]),
+        }
+    }
+
+    #[inline]
+    fn get_mut<'a>(&'a mut self, link: &'a mut NodeId) -> NodeMut<'a, NodeId> {
+        let index = link.index();
+        match link.kind() {
+            Kind::N0 => NodeMut::N0(&mut self.n0.entries[index]),
+            Kind::N4 => NodeMut::N4(&mut self.n4.entries[index]),
+            Kind::N16 => NodeMut::N16(&mut self.n16.entries[index]),
+            Kind::N48 => NodeMut::N48(&mut self.n48.entries[index]),
+            Kind::N256 => NodeMut::N256(&mut self.n256.entries[index]),
+        }
+    }
+
+    fn leaf(&mut self) -> NodeId {
+        NodeId::new(Kind::N0, self.n0.alloc(Node0::new()))
+    }
+
+    fn with_capacity(&mut self, children: usize) -> NodeId {
+        match children {
+            0 => self.leaf(),
+            1..=4 => NodeId::new(Kind::N4, self.n4.alloc(Node4::new())),
+            5..=16 => NodeId::new(Kind::N16, self.n16.alloc(Node16::new())),
+            17..=48 => NodeId::new(Kind::N48, self.n48.alloc(Node48::new())),
+            _ => NodeId::new(Kind::N256, self.n256.alloc(Node256::new())),
+        }
+    }
+
+    fn upgrade(&mut self, link: &mut NodeId) {
+        let index = link.index();
+        *link = match link.kind() {
+            Kind::N0 => {
+                let node = Node4::from(&mut self.n0.entries[index]);
+                self.n0.free(index);
+                NodeId::new(Kind::N4, self.n4.alloc(node))
+            }
+            Kind::N4 => {
+                let node = Node16::from(&mut self.n4.entries[index]);
+                self.n4.free(index);
+                NodeId::new(Kind::N16, self.n16.alloc(node))
+            }
+            Kind::N16 => {
+                let node = Node48::from(&mut self.n16.entries[index]);
+                self.n16.free(index);
+                NodeId::new(Kind::N48, self.n48.alloc(node))
+            }
+            Kind::N48 => {
+                let node = Node256::from(&mut self.n48.entries[index]);
+                self.n48.free(index);
+                NodeId::new(Kind::N256, self.n256.alloc(node))
+            }
+            Kind::N256 => unreachable!("Node256 does not grow"),
+        }
+    }
+
+    fn release(&mut self, link: NodeId) {
+        let index = link.index();
+        match link.kind() {
+            Kind::N0 => self.n0.free(index),
+            Kind::N4 => self.n4.free(index),
+            Kind::N16 => self.n16.free(index),
+            Kind::N48 => self.n48.free(index),
+            Kind::N256 => self.n256.free(index),
+        }
+    }
+
+    fn absorb(&mut self, mut other: Arena, links: &mut [NodeId]) {
+        let offsets = [
+            self.n0.entries.len(),
+            self.n4.entries.len(),
+            self.n16.entries.len(),
+            self.n48.entries.len(),
+            self.n256.entries.len(),
+        ];
+        let shift = |children: &mut [Option<NodeId>]| {
+            for child in children.iter_mut().flatten() {
+                *child = child.shift(&offsets);
+            }
+        };
+        other.n4.entries.iter_mut().for_each(|node| shift(&mut node.children));
+        other.n16.entries.iter_mut().for_each(|node| shift(&mut node.children));
+        other.n48.entries.iter_mut().for_each(|node| shift(&mut node.children));
+        other.n256.entries.iter_mut().for_each(|node| shift(&mut node.children));
+        for link in links.iter_mut() {
+            *link = link.shift(&offsets);
+        }
+
+        self.n0.append(other.n0);
+        self.n4.append(other.n4);
+        self.n16.append(other.n16);
+        self.n48.append(other.n48);
+        self.n256.append(other.n256);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn node_id_round_trips_kind_and_index() {
+        for &kind in &[Kind::N0, Kind::N4, Kind::N16, Kind::N48, Kind::N256] {
+            for &index in &[0, 1, 4096, INDEX_MASK as usize] {
+                let id = NodeId::new(kind, index);
+                assert_eq!(id.kind(), kind);
+                assert_eq!(id.index(), index);
+            }
+        }
+        assert_eq!(std::mem::size_of::<Option<NodeId>>(), 4);
+    }
+
+    #[test]
+    fn upgraded_slots_are_recycled() {
+        let mut arena = Arena::new();
+        let mut first = arena.leaf();
+        for i in 0..=255 {
+            arena.add(&mut first, &[i]);
+        }
+        assert_eq!(first.kind(), Kind::N256);
+        //the root leaf is freed by its first upgrade and reused for the first child
+        assert_eq!(arena.n0.entries.len(), 256);
+        assert!(arena.n0.free.is_empty());
+        //the Node4, Node16 and Node48 the root grew out of
+        assert_eq!(arena.n4.free.len(), 1);
+        assert_eq!(arena.n16.free.len(), 1);
+        assert_eq!(arena.n48.free.len(), 1);
+
+        //a second root growing the same way reuses every freed slot
+        let mut second = arena.leaf();
+        for i in 0..=255 {
+            arena.add(&mut second, &[i]);
+        }
+        assert_eq!(arena.n4.entries.len(), 1);
+        assert_eq!(arena.n16.entries.len(), 1);
+        assert_eq!(arena.n48.entries.len(), 1);
+        assert_eq!(arena.n256.entries.len(), 2);
+
+        for i in 0..=255 {
+            assert!(arena.exists(&first, &[i]));
+            assert!(arena.exists(&second, &[i]));
+        }
+    }
+}
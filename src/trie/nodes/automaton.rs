@@ -0,0 +1,101 @@
+use crate::trie::nodes::node::NodeRef;
+use crate::trie::nodes::store::Store;
+use std::collections::VecDeque;
+use std::ops::Range;
+
+//aho-corasick automaton over the values of a tree, one state per node
+//a state's failure link points at the state for the longest proper suffix of its path that is
+//also a path in the tree, so a scan never steps back in the text
+pub struct Automaton {
+    states: Vec<State>,
+}
+
+struct State {
+    next: Vec<(u8, usize)>, //ordered by key like the node's children
+    fail: usize,
+    depth: usize,
+    terminal: bool,
+    //nearest terminal state along the failure links, 0 when there is none
+    output: usize,
+}
+
+impl State {
+    fn goto(&self, key: u8) -> Option<usize> {
+        self.next.binary_search_by_key(&key, |(next, _)| *next).ok().map(|i| self.next[i].1)
+    }
+}
+
+impl Automaton {
+    //states are numbered breadth first, so every failure link points at a state numbered lower
+    //whose transitions are already in place
+    //the empty key can't occur anywhere worth reporting so the root is never a match
+    pub fn new<S: Store>(store: &S, root: Option<NodeRef<S::Link>>) -> Self {
+        let mut states = vec![State { next: Vec::new(), fail: 0, depth: 0, terminal: false, output: 0 }];
+        let mut pending = VecDeque::new();
+        pending.extend(root.map(|root| (0, root)));
+        while let Some((id, node)) = pending.pop_front() {
+            for (key, child) in node.children() {
+                let child = store.get(child);
+                let fail = match id {
+                    0 => 0,
+                    _ => {
+                        let mut fail = states[id].fail;
+                        loop {
+                            if let Some(next) = states[fail].goto(key) {
+                                break next;
+                            }
+                            if fail == 0 {
+                                break 0;
+                            }
+                            fail = states[fail].fail;
+                        }
+                    }
+                };
+                let output = if states[fail].terminal { fail } else { states[fail].output };
+                let next = states.len();
+                states.push(State { next: Vec::new(), fail, depth: states[id].depth + 1, terminal: child.is_terminal(), output });
+                states[id].next.push((key, next));
+                pending.push_back((next, child));
+            }
+        }
+        Automaton { states }
+    }
+
+    //every occurrence of every value in text, in the order they end, longer ones first
+    pub fn overlapping(&self, text: &[u8]) -> Vec<Range<usize>> {
+        let mut found = Vec::new();
+        let mut state = 0;
+        for (i, &byte) in text.iter().enumerate() {
+            state = loop {
+                if let Some(next) = self.states[state].goto(byte) {
+                    break next;
+                }
+                if state == 0 {
+                    break 0;
+                }
+                state = self.states[state].fail;
+            };
+            let mut matched = if self.states[state].terminal { state } else { self.states[state].output };
+            while matched != 0 {
+                found.push(i + 1 - self.states[matched].depth..i + 1);
+                matched = self.states[matched].output;
+            }
+        }
+        found
+    }
+
+    //non overlapping occurrences, of those left the one starting first and of those the longest
+    pub fn leftmost_longest(&self, text: &[u8]) -> Vec<Range<usize>> {
+        let mut found = self.overlapping(text);
+        found.sort_unstable_by_key(|found| (found.start, usize::MAX - found.end));
+        let mut end = 0;
+        found.retain(|found| {
+            let keep = found.start >= end;
+            if keep {
+                end = found.end;
+            }
+            keep
+        });
+        found
+    }
+}
@@ -0,0 +1,150 @@
+use crate::trie::nodes::node::NodeLocation::Insert;
+use crate::trie::nodes::node::{Children, Node};
+use crate::trie::nodes::store::Store;
+use std::thread;
+
+//children collected for a node whose final size isn't known yet
+struct Pending<L> {
+    terminal: bool,
+    children: Vec<(u8, L)>,
+}
+
+impl<L> Pending<L> {
+    fn new() -> Self {
+        Pending {
+            terminal: false,
+            children: Vec::new(),
+        }
+    }
+}
+
+//builds a tree from sorted values in a single pass
+//only the path of the last value is kept open, every node left behind by it
+//is complete and is allocated once at the size matching its fan-out
+pub struct SortedBuilder<L> {
+    previous: Vec<u8>,
+    //path[i] is the node reached after the first i bytes of previous
+    //entries past previous.len() are spare and kept to reuse their buffers
+    path: Vec<Pending<L>>,
+}
+
+impl<L> Default for SortedBuilder<L> {
+    fn default() -> Self {
+        SortedBuilder::new()
+    }
+}
+
+impl<L> SortedBuilder<L> {
+    pub fn new() -> Self {
+        SortedBuilder {
+            previous: Vec::new(),
+            path: vec![Pending::new()],
+        }
+    }
+
+    //values must not sort before the previous one, repeated values are ignored
+    pub fn push<S: Store<Link = L>>(&mut self, store: &mut S, value: &[u8]) -> Result<(), ()> {
+        if value < self.previous.as_slice() {
+            return Err(());
+        }
+
+        let common = value
+            .iter()
+            .zip(&self.previous)
+            .take_while(|(a, b)| a == b)
+            .count();
+        self.close(store, common);
+
+        while self.path.len() <= value.len() {
+            self.path.push(Pending::new());
+        }
+        self.path[value.len()].terminal = true;
+        self.previous.truncate(common);
+        self.previous.extend_from_slice(&value[common..]);
+        Ok(())
+    }
+
+    pub fn finish<S: Store<Link = L>>(mut self, store: &mut S) -> L {
+        self.close(store, 0);
+        build(store, &mut self.path[0])
+    }
+
+    //complete every node deeper than depth and hand it to its parent
+    fn close<S: Store<Link = L>>(&mut self, store: &mut S, depth: usize) {
+        for open in (depth + 1..=self.previous.len()).rev() {
+            let link = build(store, &mut self.path[open]);
+            let key = self.previous[open - 1];
+            self.path[open - 1].children.push((key, link));
+        }
+    }
+}
+
+//allocates the pending node and leaves the entry empty for reuse
+fn build<S: Store>(store: &mut S, pending: &mut Pending<S::Link>) -> S::Link {
+    let mut link = store.with_capacity(pending.children.len());
+    let mut node = store.get_mut(&mut link);
+    node.set_terminal(pending.terminal);
+    for (key, child) in pending.children.drain(..) {
+        match node.get_index(key) {
+            Insert(index) => node.insert_child(&index, key, child),
+            _ => unreachable!(),
+        }
+    }
+    pending.terminal = false;
+    link
+}
+
+//builds the subtree below each leading byte on its own worker, values are
+//grouped by their first byte and need not be sorted
+//the root is assembled from the subtrees the same way a bulk load would
+pub fn build_parallel<S>(store: &mut S, partitions: Vec<(u8, Vec<Vec<u8>>)>, workers: usize) -> S::Link
+where
+    S: Store + Default + Send,
+    S::Link: Send,
+{
+    //largest partitions first, each to the least loaded worker
+    let mut partitions = partitions;
+    partitions.sort_by_key(|(_, values)| std::cmp::Reverse(values.len()));
+    let mut assigned = (0..workers.max(1)).map(|_| (0, Vec::new())).collect::<Vec<_>>();
+    for partition in partitions {
+        let (load, worker) = assigned.iter_mut().min_by_key(|(load, _)| *load).unwrap();
+        *load += partition.1.len();
+        worker.push(partition);
+    }
+
+    let built = thread::scope(|scope| {
+        let handles = assigned
+            .into_iter()
+            .map(|(_, partitions)| {
+                scope.spawn(move || {
+                    let mut store = S::default();
+                    let mut keys = Vec::new();
+                    let mut links = Vec::new();
+                    for (key, mut values) in partitions {
+                        values.sort_unstable();
+                        let mut builder = SortedBuilder::new();
+                        for value in &values {
+                            //sorted just above so this can't fail
+                            builder.push(&mut store, &value[1..]).unwrap();
+                        }
+                        keys.push(key);
+                        links.push(builder.finish(&mut store));
+                    }
+                    (store, keys, links)
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+
+    let mut root = Pending::new();
+    for (worker_store, keys, mut links) in built {
+        store.absorb(worker_store, &mut links);
+        root.children.extend(keys.into_iter().zip(links));
+    }
+    root.children.sort_unstable_by_key(|(key, _)| *key);
+    build(store, &mut root)
+}
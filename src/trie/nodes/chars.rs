@@ -0,0 +1,50 @@
+use crate::trie::nodes::node::NodeLocation::Exists;
+use crate::trie::nodes::node::NodeRef;
+use crate::trie::nodes::store::Store;
+
+//bytes in the utf-8 character starting with first
+//continuation and other invalid leading bytes stand alone, they become a replacement character
+pub fn char_len(first: u8) -> usize {
+    match first {
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => 1,
+    }
+}
+
+//nodes one whole character below node with the character and its bytes, in byte order
+//storage only ever branches on bytes, so the bytes of a character are gathered across levels
+//a value ending partway through a character is never reached
+pub fn char_children<'a, S: Store>(store: &'a S, node: NodeRef<'a, S::Link>) -> Vec<(char, Vec<u8>, NodeRef<'a, S::Link>)> {
+    let mut found = Vec::new();
+    let mut pending = vec![(node, Vec::new())];
+    while let Some((node, bytes)) = pending.pop() {
+        for (key, child) in node.children().into_iter().rev() {
+            let mut bytes = bytes.clone();
+            bytes.push(key);
+            let child = store.get(child);
+            if bytes.len() == char_len(bytes[0]) {
+                let c = std::str::from_utf8(&bytes).ok().and_then(|c| c.chars().next()).unwrap_or(char::REPLACEMENT_CHARACTER);
+                found.push((c, bytes, child));
+            } else {
+                pending.push((child, bytes));
+            }
+        }
+    }
+    //the stack hands back every multi byte character after the single byte ones before it
+    found.sort_unstable_by(|(_, a, _), (_, b, _)| a.cmp(b));
+    found
+}
+
+//node reached by following the bytes of c
+pub fn char_child<'a, S: Store>(store: &'a S, node: NodeRef<'a, S::Link>, c: char) -> Option<NodeRef<'a, S::Link>> {
+    let mut node = node;
+    for byte in c.encode_utf8(&mut [0; 4]).bytes() {
+        node = match node.get_index(byte) {
+            Exists(index) => store.get(node.get_child(index.child)?),
+            _ => return None,
+        };
+    }
+    Some(node)
+}
@@ -0,0 +1,52 @@
+use crate::trie::nodes::node::NodeRef;
+use crate::trie::nodes::store::Store;
+use std::fmt::Write;
+
+//graphviz description of the nodes down to max_depth, the root is at depth 0
+//each node shows its type and keys, terminal nodes get a double border and edges are labelled
+//with their byte, nodes with children past max_depth point at a dashed count of what was left out
+pub fn dot<S: Store>(store: &S, root: NodeRef<S::Link>, max_depth: usize) -> String {
+    let mut out = String::from("digraph tree {\n    node [shape=box, fontname=\"monospace\"];\n");
+    let mut next = 1;
+    let mut pending = vec![(root, 0, 0)];
+    while let Some((node, id, depth)) = pending.pop() {
+        let children = node.children();
+        let keys = children.iter().map(|(key, _)| byte(*key)).collect::<Vec<_>>();
+        let _ = write!(out, "    n{} [label=\"{:?}", id, node.kind());
+        if !keys.is_empty() {
+            let _ = write!(out, "\\n{}", keys.join(" "));
+        }
+        out.push('"');
+        if node.is_terminal() {
+            out.push_str(", peripheries=2");
+        }
+        out.push_str("];\n");
+
+        if depth == max_depth {
+            if !children.is_empty() {
+                let _ = writeln!(out, "    n{}_more [label=\"{} more\", shape=plaintext];", id, children.len());
+                let _ = writeln!(out, "    n{} -> n{}_more [style=dashed];", id, id);
+            }
+            continue;
+        }
+        //children are numbered in key order and pushed in reverse so they're also written in key order
+        for (i, (key, _)) in children.iter().enumerate() {
+            let _ = writeln!(out, "    n{} -> n{} [label=\"{}\"];", id, next + i, byte(*key));
+        }
+        for (i, (_, child)) in children.iter().enumerate().rev() {
+            pending.push((store.get(child), next + i, depth + 1));
+        }
+        next += children.len();
+    }
+    out.push_str("}\n");
+    out
+}
+
+//printable ascii as itself, anything that would need escaping inside a dot string as hex
+fn byte(key: u8) -> String {
+    match key {
+        b'"' | b'\\' => format!("0x{:02x}", key),
+        key if key.is_ascii_graphic() => (key as char).to_string(),
+        key => format!("0x{:02x}", key),
+    }
+}
@@ -0,0 +1,43 @@
+use crate::trie::nodes::chars::char_children;
+use crate::trie::nodes::node::NodeRef;
+use crate::trie::nodes::store::Store;
+
+//values within max edits of query in byte order, edits are character insertions, deletions and substitutions
+//each character below a node extends the node's row of the edit distance table by one entry
+//and subtrees are skipped once every entry in the row is over max
+pub fn fuzzy<S: Store>(store: &S, root: NodeRef<S::Link>, query: &[char], max: usize) -> Vec<Vec<u8>> {
+    let row = (0..=query.len()).collect::<Vec<_>>();
+    let mut found = Vec::new();
+    walk(store, root, query, max, &row, &mut Vec::new(), &mut found);
+    found
+}
+
+fn walk<S: Store>(
+    store: &S,
+    node: NodeRef<S::Link>,
+    query: &[char],
+    max: usize,
+    row: &[usize],
+    value: &mut Vec<u8>,
+    found: &mut Vec<Vec<u8>>,
+) {
+    if node.is_terminal() && row[query.len()] <= max {
+        found.push(value.clone());
+    }
+    if row.iter().all(|&distance| distance > max) {
+        return;
+    }
+
+    for (key, bytes, child) in char_children(store, node) {
+        let mut next = Vec::with_capacity(row.len());
+        next.push(row[0] + 1);
+        for (i, &c) in query.iter().enumerate() {
+            let substitute = row[i] + (c != key) as usize;
+            next.push(substitute.min(row[i + 1] + 1).min(next[i] + 1));
+        }
+        let length = value.len();
+        value.extend_from_slice(&bytes);
+        walk(store, child, query, max, &next, value, found);
+        value.truncate(length);
+    }
+}
@@ -0,0 +1,67 @@
+use crate::trie::nodes::node::NodeRef;
+use crate::trie::nodes::store::Store;
+
+//terminal values below a node in byte order
+//depth first, a node's own value comes before its children's and children go by key
+pub struct Values<'a, S: Store> {
+    store: &'a S,
+    //nodes still to visit with the bytes leading to them, the next one is last
+    pending: Vec<(NodeRef<'a, S::Link>, Vec<u8>)>,
+}
+
+impl<'a, S: Store> Values<'a, S> {
+    pub fn new(store: &'a S, node: Option<NodeRef<'a, S::Link>>, prefix: &[u8]) -> Self {
+        Values {
+            store,
+            pending: node.into_iter().map(|node| (node, prefix.to_vec())).collect(),
+        }
+    }
+
+    //values below root that sort at or after start
+    //only the subtrees beside the path to start are queued, everything before it is skipped
+    pub fn seek(store: &'a S, root: Option<NodeRef<'a, S::Link>>, start: &[u8]) -> Self {
+        let mut values = Values::new(store, None, &[]);
+        let mut node = match root {
+            Some(root) => root,
+            None => return values,
+        };
+        for (depth, byte) in start.iter().enumerate() {
+            //the node's own value is a proper prefix of start so it sorts before it
+            let mut next = None;
+            for (key, child) in node.children().into_iter().rev() {
+                if key > *byte {
+                    let mut value = start[..depth].to_vec();
+                    value.push(key);
+                    values.pending.push((store.get(child), value));
+                } else if key == *byte {
+                    next = Some(store.get(child));
+                }
+            }
+            node = match next {
+                Some(next) => next,
+                None => return values,
+            };
+        }
+        values.pending.push((node, start.to_vec()));
+        values
+    }
+}
+
+impl<S: Store> Iterator for Values<'_, S> {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        while let Some((node, value)) = self.pending.pop() {
+            for (key, child) in node.children().into_iter().rev() {
+                let mut child_value = Vec::with_capacity(value.len() + 1);
+                child_value.extend_from_slice(&value);
+                child_value.push(key);
+                self.pending.push((self.store.get(child), child_value));
+            }
+            if node.is_terminal() {
+                return Some(value);
+            }
+        }
+        None
+    }
+}
@@ -1,6 +1,19 @@
+pub(crate) mod arena;
+pub(crate) mod automaton;
+pub(crate) mod bulk;
+pub(crate) mod chars;
+pub(crate) mod dot;
+pub(crate) mod fuzzy;
+pub(crate) mod iter;
 pub(crate) mod node;
 pub(crate) mod node0;
 pub(crate) mod node16;
 pub(crate) mod node256;
 pub(crate) mod node4;
 pub(crate) mod node48;
+pub(crate) mod segment;
+pub(crate) mod serialize;
+pub(crate) mod store;
+pub(crate) mod validate;
+pub(crate) mod versioned;
+pub(crate) mod wildcard;
@@ -1,57 +1,198 @@
-use std::any::Any;
-
 use std::cmp::Ordering;
-use std::fmt::Debug;
-use crate::trie::nodes::node::NodeLocation::{Exists, Insert, Upgrade};
+use std::sync::Arc;
+use crate::trie::enums::NodeKind;
+use crate::trie::nodes::{
+    node0::Node0, node16::Node16, node256::Node256, node4::Node4, node48::Node48,
+};
 
-pub trait Node: Debug {
+pub trait Node {
     fn is_full(&self) -> bool;
+    //no children, regardless of whether the node is terminal
     fn is_empty(&self) -> bool;
     fn is_terminal(&self) -> bool;
     fn set_terminal(&mut self, terminal: bool);
-    fn as_any(&self) -> &dyn Any;
-
-    fn add(&mut self, values: &[u8]) -> NodeOption {
-        if let Some((first, rest)) = values.split_first() {
-            match &self.get_index(*first) {
-                Exists(index) => self.exists_add(index, rest),
-                Insert(index) => self.insert_add(index, *first, rest),
-                Upgrade => self.upgrade_add(values),
-            }
-        } else {
-            self.set_terminal(true);
-            None
+    fn get_index(&self, value: u8) -> NodeLocation;
+}
+
+//L is the link a node uses to refer to its children, see store.rs
+pub trait Children<L> {
+    fn get_child(&self, index: usize) -> Option<&L>;
+    fn take_child(&mut self, index: usize) -> Option<L>;
+    fn set_child(&mut self, index: usize, child: L);
+    fn insert_child(&mut self, index: &KeyChildIndex, key: u8, child: L);
+    //index as returned by get_index for a child that exists
+    fn remove_child(&mut self, index: &KeyChildIndex) -> Option<L>;
+}
+
+//closed set of node kinds, dispatched with a match instead of a vtable
+//leaves carry only the terminal flag so they are stored inline rather than boxed
+//nodes are reference counted so trees can share subtrees, a shared node is copied before it's modified
+#[derive(Debug, Clone)]
+pub enum NodeType {
+    N0(Node0),
+    N4(Arc<Node4<NodeType>>),
+    N16(Arc<Node16<NodeType>>),
+    N48(Arc<Node48<NodeType>>),
+    N256(Arc<Node256<NodeType>>),
+}
+
+//see: https://www.the-paper-trail.org/post/art-paper-notes/
+pub type NodeOption = Option<NodeType>;
+
+//borrowed view of a node independent of where the node is stored
+pub enum NodeRef<'a, L> {
+    N0(&'a Node0),
+    N4(&'a Node4<L>),
+    N16(&'a Node16<L>),
+    N48(&'a Node48<L>),
+    N256(&'a Node256<L>),
+}
+
+pub enum NodeMut<'a, L> {
+    N0(&'a mut Node0),
+    N4(&'a mut Node4<L>),
+    N16(&'a mut Node16<L>),
+    N48(&'a mut Node48<L>),
+    N256(&'a mut Node256<L>),
+}
+
+macro_rules! dispatch {
+    ($kind:ident, $node:expr, $n:ident => $call:expr) => {
+        match $node {
+            $kind::N0($n) => $call,
+            $kind::N4($n) => $call,
+            $kind::N16($n) => $call,
+            $kind::N48($n) => $call,
+            $kind::N256($n) => $call,
         }
+    };
+}
+
+impl<L> Clone for NodeRef<'_, L> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<L> Copy for NodeRef<'_, L> {}
+
+//children are returned with the lifetime of the store rather than of the view
+//so lookups can walk down the tree without holding on to each parent
+impl<'a, L> NodeRef<'a, L> {
+    pub fn kind(self) -> NodeKind {
+        match self {
+            NodeRef::N0(_) => NodeKind::Node0,
+            NodeRef::N4(_) => NodeKind::Node4,
+            NodeRef::N16(_) => NodeKind::Node16,
+            NodeRef::N48(_) => NodeKind::Node48,
+            NodeRef::N256(_) => NodeKind::Node256,
+        }
+    }
+
+    #[inline]
+    pub fn is_terminal(self) -> bool {
+        dispatch!(NodeRef, self, n => n.is_terminal())
+    }
+
+    #[inline]
+    pub fn is_empty(self) -> bool {
+        dispatch!(NodeRef, self, n => n.is_empty())
+    }
+
+    #[inline]
+    pub fn get_index(self, value: u8) -> NodeLocation {
+        dispatch!(NodeRef, self, n => n.get_index(value))
     }
 
-    fn exists(&self, values: &[u8]) -> bool {
-        if let Some((first, rest)) = values.split_first() {
-            match self.get_index(*first) {
-                Exists(index) => {
-                    if let Some(child) = self.get_child(index.child) {
-                        child.exists(rest)
-                    } else {
-                        false
-                    }
-                }
-                _ => false
+    #[inline]
+    pub fn get_child(self, index: usize) -> Option<&'a L> {
+        dispatch!(NodeRef, self, n => n.get_child(index))
+    }
+
+    //every child with its key, ordered by key
+    pub fn children(self) -> Vec<(u8, &'a L)> {
+        match self {
+            NodeRef::N0(_) => Vec::new(),
+            NodeRef::N4(n) => {
+                let mut children = n
+                    .keys
+                    .iter()
+                    .zip(n.children.iter())
+                    .filter_map(|(key, child)| Some(((*key)?, child.as_ref()?)))
+                    .collect::<Vec<_>>();
+                children.sort_unstable_by_key(|(key, _)| *key);
+                children
             }
-        } else {
-            self.is_terminal()
+            NodeRef::N16(n) => n.keys[..n.size]
+                .iter()
+                .zip(n.children.iter())
+                .filter_map(|(key, child)| Some((*key, child.as_ref()?)))
+                .collect(),
+            NodeRef::N48(n) => (0..=255u8)
+                .zip(n.keys.iter())
+                .filter_map(|(key, slot)| Some((key, n.children[(*slot)? as usize].as_ref()?)))
+                .collect(),
+            NodeRef::N256(n) => (0..=255u8)
+                .zip(n.children.iter())
+                .filter_map(|(key, child)| Some((key, child.as_ref()?)))
+                .collect(),
         }
     }
+}
 
-    //TODO break into different trait
-    fn get_index(&self, value: u8) -> NodeLocation;
-    fn get_child(&self, index: usize) -> Option<&Box<dyn Node>>;
-    fn exists_add(&mut self, index: &KeyChildIndex, rest: &[u8]) -> NodeOption;
-    fn insert_add(&mut self, index: &KeyChildIndex, first: u8, rest: &[u8]) -> NodeOption;
-    fn upgrade_add(&mut self, values: &[u8]) -> NodeOption;
+impl<L> Node for NodeMut<'_, L> {
+    #[inline]
+    fn is_full(&self) -> bool {
+        dispatch!(NodeMut, self, n => n.is_full())
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        dispatch!(NodeMut, self, n => n.is_empty())
+    }
+
+    #[inline]
+    fn is_terminal(&self) -> bool {
+        dispatch!(NodeMut, self, n => n.is_terminal())
+    }
+
+    #[inline]
+    fn set_terminal(&mut self, terminal: bool) {
+        dispatch!(NodeMut, self, n => n.set_terminal(terminal))
+    }
+
+    #[inline]
+    fn get_index(&self, value: u8) -> NodeLocation {
+        dispatch!(NodeMut, self, n => n.get_index(value))
+    }
 }
 
+impl<L> Children<L> for NodeMut<'_, L> {
+    #[inline]
+    fn get_child(&self, index: usize) -> Option<&L> {
+        dispatch!(NodeMut, self, n => n.get_child(index))
+    }
+
+    #[inline]
+    fn take_child(&mut self, index: usize) -> Option<L> {
+        dispatch!(NodeMut, self, n => n.take_child(index))
+    }
 
-//see: https://www.the-paper-trail.org/post/art-paper-notes/
-pub type NodeOption = Option<Box<dyn Node>>;
+    #[inline]
+    fn set_child(&mut self, index: usize, child: L) {
+        dispatch!(NodeMut, self, n => n.set_child(index, child))
+    }
+
+    #[inline]
+    fn insert_child(&mut self, index: &KeyChildIndex, key: u8, child: L) {
+        dispatch!(NodeMut, self, n => n.insert_child(index, key, child))
+    }
+
+    #[inline]
+    fn remove_child(&mut self, index: &KeyChildIndex) -> Option<L> {
+        dispatch!(NodeMut, self, n => n.remove_child(index))
+    }
+}
 
 pub enum NodeLocation {
     Exists(KeyChildIndex), //key child
@@ -79,9 +220,7 @@ pub fn val_cmp(a: &Option<u8>, b: &Option<u8>) -> Ordering {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::trie::nodes::{
-        node0::Node0, node16::Node16, node256::Node256, node4::Node4, node48::Node48,
-    };
+    use crate::trie::nodes::store::{Heap, Store};
 
     // #[test]
     // fn trial_run_test() {
@@ -103,65 +242,33 @@ mod tests {
 
     #[test]
     fn test_all_upgrades_occur_exact_match() {
-        let mut node = NodeOption::Some(Box::new(Node0::new()));
+        let mut node = Heap.leaf();
         for i in 0..=3 {
-            let upgrade = node.as_mut().unwrap().add(&[i]);
-            if upgrade.is_some() {
-                node = upgrade;
-            }
+            Heap.add(&mut node, &[i]);
         }
 
-        assert!(node
-            .as_ref()
-            .unwrap()
-            .as_any()
-            .downcast_ref::<Node4>()
-            .is_some());
-        assert!(node.as_ref().unwrap().is_full());
+        assert!(matches!(node, NodeType::N4(_)));
+        assert!(Heap.get_mut(&mut node).is_full());
 
         for i in 4..=15 {
-            let upgrade = node.as_mut().unwrap().add(&[i]);
-            if upgrade.is_some() {
-                node = upgrade;
-            }
+            Heap.add(&mut node, &[i]);
         }
 
-        assert!(node
-            .as_ref()
-            .unwrap()
-            .as_any()
-            .downcast_ref::<Node16>()
-            .is_some());
-        assert!(node.as_ref().unwrap().is_full());
+        assert!(matches!(node, NodeType::N16(_)));
+        assert!(Heap.get_mut(&mut node).is_full());
 
         for i in 16..=47 {
-            let upgrade = node.as_mut().unwrap().add(&[i]);
-            if upgrade.is_some() {
-                node = upgrade;
-            }
+            Heap.add(&mut node, &[i]);
         }
 
-        assert!(node
-            .as_ref()
-            .unwrap()
-            .as_any()
-            .downcast_ref::<Node48>()
-            .is_some());
-        assert!(node.as_ref().unwrap().is_full());
+        assert!(matches!(node, NodeType::N48(_)));
+        assert!(Heap.get_mut(&mut node).is_full());
 
         for i in 48..=255 {
-            let upgrade = node.as_mut().unwrap().add(&[i]);
-            if upgrade.is_some() {
-                node = upgrade;
-            }
+            Heap.add(&mut node, &[i]);
         }
 
-        assert!(node
-            .as_ref()
-            .unwrap()
-            .as_any()
-            .downcast_ref::<Node256>()
-            .is_some());
-        assert!(node.as_ref().unwrap().is_full());
+        assert!(matches!(node, NodeType::N256(_)));
+        assert!(Heap.get_mut(&mut node).is_full());
     }
 }
@@ -1,9 +1,7 @@
-use crate::trie::nodes::node::{KeyChildIndex, Node, NodeLocation, NodeOption};
-use crate::trie::nodes::node4::Node4;
-use std::any::Any;
+use crate::trie::nodes::node::{Children, KeyChildIndex, Node, NodeLocation};
 
 //TODO performance and memory test storing children directly in keys
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Node0 {
     pub(crate) terminal: bool,
 }
@@ -26,7 +24,7 @@ impl Node for Node0 {
     }
 
     fn is_empty(&self) -> bool {
-        false
+        true
     }
 
     fn is_terminal(&self) -> bool {
@@ -37,29 +35,29 @@ impl Node for Node0 {
         self.terminal = terminal
     }
 
-    fn as_any(&self) -> &dyn Any {
-        self
+    fn get_index(&self, _value: u8) -> NodeLocation {
+        NodeLocation::Upgrade
     }
+}
 
-    fn get_index(&self, value: u8) -> NodeLocation {
-        NodeLocation::Upgrade
+impl<L> Children<L> for Node0 {
+    fn get_child(&self, _index: usize) -> Option<&L> {
+        None
     }
 
-    fn get_child(&self, index: usize) -> Option<&Box<dyn Node>> {
-        None.as_ref()
+    fn take_child(&mut self, _index: usize) -> Option<L> {
+        None
     }
 
-    fn exists_add(&mut self, index: &KeyChildIndex, rest: &[u8]) -> NodeOption {
-        unimplemented!()
+    fn set_child(&mut self, _index: usize, _child: L) {
+        unreachable!("Node0 holds no children, it is upgraded before one is added")
     }
 
-    fn insert_add(&mut self, index: &KeyChildIndex, first: u8, rest: &[u8]) -> NodeOption {
-        unimplemented!()
+    fn insert_child(&mut self, _index: &KeyChildIndex, _key: u8, _child: L) {
+        unreachable!("Node0 holds no children, it is upgraded before one is added")
     }
 
-    fn upgrade_add(&mut self, values: &[u8]) -> NodeOption {
-        let mut new_node = Node4::from(self);
-        new_node.add(values);
-        Some(Box::new(new_node))
+    fn remove_child(&mut self, _index: &KeyChildIndex) -> Option<L> {
+        None
     }
 }
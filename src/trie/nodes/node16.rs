@@ -1,35 +1,34 @@
-use crate::trie::nodes::node::{val_cmp, Node, NodeOption, NodeLocation, KeyChildIndex};
-use crate::trie::nodes::{node0::Node0, node4::Node4, node48::Node48};
+use crate::trie::nodes::node::{val_cmp, Children, Node, NodeLocation, KeyChildIndex};
+use crate::trie::nodes::node4::Node4;
 use arr_macro::arr;
-use std::any::Any;
 use crate::trie::nodes::node::NodeLocation::{Exists, Insert, Upgrade};
 
-#[derive(Debug)]
-pub struct Node16 {
-    pub(crate) keys: [Option<u8>; 16],
-    pub(crate) children: [NodeOption; 16], //value represents value with matching node in children index
+#[derive(Debug, Clone)]
+pub struct Node16<L> {
+    pub(crate) keys: [u8; 16], //only the first size keys are meaningful
+    pub(crate) children: [Option<L>; 16], //value represents value with matching node in children index
     pub(crate) size: usize,
     pub(crate) terminal: bool,
 }
 
-impl Default for Node16 {
+impl<L> Default for Node16<L> {
     fn default() -> Self {
         Node16::new()
     }
 }
 
-impl Node16 {
+impl<L> Node16<L> {
     //keys stored sorted
     pub fn new() -> Self {
         Node16 {
-            keys: [None; 16],
+            keys: [0; 16],
             children: arr![None; 16],
             size: 0,
             terminal: false,
         }
     }
 
-    pub fn from(node: &mut Node4) -> Self {
+    pub fn from(node: &mut Node4<L>) -> Self {
         let mut new_node = Node16::new();
         //sort the keys and original indices of the keys
         //the original indices will be used to create new arrays with the correct order
@@ -41,8 +40,9 @@ impl Node16 {
             .map(|(index, _)| *index)
             .collect::<Vec<_>>();
         //order arrays based on the ordered indices
-        for (target_i, source_i) in ordered_index.iter().enumerate() {
-            new_node.keys[target_i] = node.keys[(*source_i)].take();
+        //empty keys sort last so the first size entries are all occupied
+        for (target_i, source_i) in ordered_index.iter().take(node.size).enumerate() {
+            new_node.keys[target_i] = node.keys[*source_i].take().unwrap();
             new_node.children[target_i] = node.children[*source_i].take();
         }
 
@@ -52,7 +52,7 @@ impl Node16 {
     }
 }
 
-impl Node for Node16 {
+impl<L> Node for Node16<L> {
     fn is_full(&self) -> bool {
         self.size == self.children.len()
     }
@@ -69,13 +69,8 @@ impl Node for Node16 {
         self.terminal = terminal
     }
 
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-
     fn get_index(&self, value: u8) -> NodeLocation {
-        match self.keys
-            .binary_search_by(|probe| val_cmp(probe, &Some(value))) {
+        match search(&self.keys, self.size, value) {
             Ok(index) => {
                 Exists(KeyChildIndex{key: index, child: index})
             }
@@ -88,39 +83,132 @@ impl Node for Node16 {
             }
         }
     }
+}
 
-    fn get_child(&self, index: usize) -> Option<&Box<dyn Node>> {
+impl<L> Children<L> for Node16<L> {
+    fn get_child(&self, index: usize) -> Option<&L> {
         self.children[index].as_ref()
     }
 
-    fn exists_add(&mut self, index: &KeyChildIndex, rest: &[u8]) -> NodeOption {
-        let upgraded_node = self.children[index.child]
-            .get_or_insert_with(|| Box::new(Node0::new()))
-            .as_mut()
-            .add(rest);
-        if upgraded_node.is_some() {
-            self.children[index.child] = upgraded_node;
-        }
-        None
+    fn take_child(&mut self, index: usize) -> Option<L> {
+        self.children[index].take()
     }
 
-    fn insert_add(&mut self, index: &KeyChildIndex, first: u8, rest: &[u8]) -> NodeOption {
+    fn set_child(&mut self, index: usize, child: L) {
+        self.children[index] = Some(child);
+    }
+
+    fn insert_child(&mut self, index: &KeyChildIndex, key: u8, child: L) {
         // add value in sorted order to existing Node16 if there is room
         self.keys[index.key..].rotate_right(1); //shift right from index
-        self.keys[index.key] = Some(first);
+        self.keys[index.key] = key;
 
         self.children[index.child..].rotate_right(1);
-        let mut new_node = Node0::new();
-        self.children[index.child] = new_node.add(rest).or_else(|| Some(Box::new(new_node)));
+        self.children[index.child] = Some(child);
 
         self.size += 1;
-        None
     }
 
-    fn upgrade_add(&mut self, values: &[u8]) -> NodeOption {
-        //expand to node48 and then add new value
-        let mut upgraded_node = Node48::from(self);
-        upgraded_node.add(values);
-        Some(Box::new(upgraded_node))
+    fn remove_child(&mut self, index: &KeyChildIndex) -> Option<L> {
+        //shift left over the removed key to keep the rest sorted
+        self.keys[index.key..].rotate_left(1);
+        let child = self.children[index.child].take();
+        self.children[index.child..].rotate_left(1);
+
+        self.size -= 1;
+        child
+    }
+}
+
+//find value in the sorted keys[..size] with the same contract as binary_search:
+//Ok(index) when found, Err(index) with the insertion point otherwise
+#[cfg(target_arch = "x86_64")]
+fn search(keys: &[u8; 16], size: usize, value: u8) -> Result<usize, usize> {
+    use std::arch::x86_64::*;
+    //sse2 is part of the x86_64 baseline so no runtime detection is needed
+    unsafe {
+        let occupied = (1u32 << size) - 1;
+        let keys = _mm_loadu_si128(keys.as_ptr() as *const __m128i);
+        let value = _mm_set1_epi8(value as i8);
+        let equal = _mm_movemask_epi8(_mm_cmpeq_epi8(keys, value)) as u32 & occupied;
+        if equal != 0 {
+            return Ok(equal.trailing_zeros() as usize);
+        }
+        //sse2 only compares signed bytes, flipping the sign bit gives an unsigned compare
+        let flip = _mm_set1_epi8(i8::MIN);
+        let less = _mm_cmplt_epi8(_mm_xor_si128(keys, flip), _mm_xor_si128(value, flip));
+        Err((_mm_movemask_epi8(less) as u32 & occupied).count_ones() as usize)
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+fn search(keys: &[u8; 16], size: usize, value: u8) -> Result<usize, usize> {
+    use std::arch::aarch64::*;
+    //neon has no movemask, narrowing each 16 bit lane by 4 leaves a nibble per key
+    unsafe fn mask(lanes: uint8x16_t) -> u64 {
+        let narrowed = vshrn_n_u16::<4>(vreinterpretq_u16_u8(lanes));
+        vget_lane_u64::<0>(vreinterpret_u64_u8(narrowed))
+    }
+    unsafe {
+        let occupied = if size == 16 { u64::MAX } else { (1u64 << (size * 4)) - 1 };
+        let keys = vld1q_u8(keys.as_ptr());
+        let value = vdupq_n_u8(value);
+        let equal = mask(vceqq_u8(keys, value)) & occupied;
+        if equal != 0 {
+            return Ok(equal.trailing_zeros() as usize / 4);
+        }
+        Err((mask(vcltq_u8(keys, value)) & occupied).count_ones() as usize / 4)
+    }
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn search(keys: &[u8; 16], size: usize, value: u8) -> Result<usize, usize> {
+    portable_search(keys, size, value)
+}
+
+#[cfg_attr(any(target_arch = "x86_64", target_arch = "aarch64"), allow(dead_code))]
+fn portable_search(keys: &[u8; 16], size: usize, value: u8) -> Result<usize, usize> {
+    keys[..size].binary_search(&value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trie::nodes::node::NodeType;
+    use crate::trie::nodes::store::{Heap, Store};
+
+    #[test]
+    fn search_matches_portable_search() {
+        //every size with spread out keys, including the extremes of the unsigned range
+        for size in 0..=16 {
+            let mut keys = [0; 16];
+            for (i, key) in keys.iter_mut().enumerate().take(size) {
+                *key = (i * 17) as u8;
+            }
+            for value in 0..=255 {
+                assert_eq!(
+                    search(&keys, size, value),
+                    portable_search(&keys, size, value)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn keys_stay_sorted_through_upgrades() {
+        let added = [200, 3, 255, 0, 128, 127, 1, 254, 64, 65, 190, 9, 10, 77, 250, 129];
+        let mut node = Heap.leaf();
+        for &i in &added {
+            Heap.add(&mut node, &[i]);
+        }
+
+        if let NodeType::N16(node16) = &node {
+            assert!(node16.keys.windows(2).all(|w| w[0] < w[1]));
+        } else {
+            panic!()
+        }
+        for i in 0..=255 {
+            assert_eq!(Heap.exists(&node, &[i]), added.contains(&i));
+        }
     }
 }
@@ -1,22 +1,21 @@
-use crate::trie::nodes::node::{KeyChildIndex, Node, NodeLocation, NodeOption};
-use crate::trie::nodes::{node0::Node0, node48::Node48};
+use crate::trie::nodes::node::{Children, KeyChildIndex, Node, NodeLocation};
+use crate::trie::nodes::node48::Node48;
 use arr_macro::arr;
-use std::any::Any;
 
-#[derive(Debug)]
-pub struct Node256 {
-    pub(crate) children: [NodeOption; 256],
+#[derive(Debug, Clone)]
+pub struct Node256<L> {
+    pub(crate) children: [Option<L>; 256],
     pub(crate) size: usize,
     pub(crate) terminal: bool,
 }
 
-impl Default for Node256 {
+impl<L> Default for Node256<L> {
     fn default() -> Self {
         Node256::new()
     }
 }
 
-impl Node256 {
+impl<L> Node256<L> {
     pub fn new() -> Self {
         Node256 {
             children: arr![None; 256],
@@ -25,7 +24,7 @@ impl Node256 {
         }
     }
 
-    pub fn from(node: &mut Node48) -> Self {
+    pub fn from(node: &mut Node48<L>) -> Self {
         let mut new_node = Node256::new();
 
         for (index, key) in node.keys.iter().enumerate() {
@@ -41,7 +40,7 @@ impl Node256 {
     }
 }
 
-impl Node for Node256 {
+impl<L> Node for Node256<L> {
     fn is_full(&self) -> bool {
         self.size == self.children.len()
     }
@@ -58,10 +57,6 @@ impl Node for Node256 {
         self.terminal = terminal
     }
 
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-
     fn get_index(&self, value: u8) -> NodeLocation {
         let cur_value_index = value as usize;
         if self.children[cur_value_index].is_some() {
@@ -70,62 +65,59 @@ impl Node for Node256 {
             NodeLocation::Insert(KeyChildIndex{key: 0, child: cur_value_index})
         }
     }
+}
 
-    fn get_child(&self, index: usize) -> Option<&Box<dyn Node>> {
+impl<L> Children<L> for Node256<L> {
+    fn get_child(&self, index: usize) -> Option<&L> {
         self.children[index].as_ref()
     }
 
-    fn exists_add(&mut self, index: &KeyChildIndex, rest: &[u8]) -> NodeOption {
-        let upgraded_node = self.children[index.child]
-            .get_or_insert_with(|| Box::new(Node0::new()))
-            .as_mut()
-            .add(rest);
-        if upgraded_node.is_some() {
-            self.children[index.child] = upgraded_node;
-        }
-        None
+    fn take_child(&mut self, index: usize) -> Option<L> {
+        self.children[index].take()
     }
 
-    fn insert_add(&mut self, index: &KeyChildIndex, first: u8, rest: &[u8]) -> NodeOption {
-        let mut new_node = Node0::new();
-        self.children[index.child] = new_node.add(rest).or_else(|| Some(Box::new(new_node)));
+    fn set_child(&mut self, index: usize, child: L) {
+        self.children[index] = Some(child);
+    }
+
+    fn insert_child(&mut self, index: &KeyChildIndex, _key: u8, child: L) {
+        self.children[index.child] = Some(child);
         self.size += 1;
-        None
     }
 
-    fn upgrade_add(&mut self, values: &[u8]) -> NodeOption {
-        unimplemented!()
+    fn remove_child(&mut self, index: &KeyChildIndex) -> Option<L> {
+        self.size -= 1;
+        self.children[index.child].take()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-    use crate::trie::nodes::node4::Node4;
+    use crate::trie::nodes::node::NodeType;
+    use crate::trie::nodes::store::{Heap, Store};
+    use std::sync::Arc;
 
     #[test]
     fn order_preserved_256_exact_match() {
-        let mut node = NodeOption::Some(Box::new(Node4::new()));
+        let mut node = NodeType::N4(Arc::default());
 
         for i in 0..=255 {
             if i % 2 == 0 {
-                let upgrade = node.as_mut().unwrap().add(&[i]);
-                if upgrade.is_some() {
-                    node = upgrade;
-                }
+                Heap.add(&mut node, &[i]);
             }
         }
 
         // println!("{:#?}", node);
 
-        if let Some(n) = node {
-            let node256 = n.as_any().downcast_ref::<Node256>().unwrap();
+        if let NodeType::N256(node256) = node {
             for (i, c) in node256.children.iter().enumerate() {
                 match &c {
                     None => assert_ne!(i % 2, 0),
                     Some(_) => assert_eq!(i % 2, 0),
                 }
             }
+        } else {
+            panic!()
         }
     }
 }
@@ -1,13 +1,12 @@
-use crate::trie::nodes::node::{KeyChildIndex, Node, NodeLocation, NodeOption};
-use crate::trie::nodes::{node0::Node0, node16::Node16};
+use crate::trie::nodes::node::{Children, KeyChildIndex, Node, NodeLocation};
+use crate::trie::nodes::node0::Node0;
 use arr_macro::arr;
-use std::any::Any;
 use crate::trie::nodes::node::NodeLocation::{Exists, Insert, Upgrade};
 
-#[derive(Debug)]
-pub struct Node4 {
+#[derive(Debug, Clone)]
+pub struct Node4<L> {
     pub(crate) keys: [Option<u8>; 4], //FIXME: Can remove this option and rely only on children option
-    pub(crate) children: [NodeOption; 4],
+    pub(crate) children: [Option<L>; 4],
     pub(crate) size: usize,
     pub(crate) terminal: bool,
 }
@@ -24,7 +23,7 @@ pub struct Node4 {
 //How to have both uncompressed and compressed in a single array
 //optional: can add expand and collapse method to tree
 
-impl Node4 {
+impl<L> Node4<L> {
     pub fn new() -> Self {
         Node4 {
             keys: [None; 4],
@@ -44,13 +43,13 @@ impl Node4 {
     // maybe have a pre-processing and post-processing capabilities along with arbitrary data storage per node
 }
 
-impl Default for Node4 {
+impl<L> Default for Node4<L> {
     fn default() -> Self {
         Node4::new()
     }
 }
 
-impl Node for Node4 {
+impl<L> Node for Node4<L> {
     fn is_full(&self) -> bool {
         self.size == self.children.len()
     }
@@ -67,10 +66,6 @@ impl Node for Node4 {
         self.terminal = terminal
     }
 
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-
     fn get_index(&self, value: u8) -> NodeLocation {
         if let Some(index) = self.keys
             .iter()
@@ -82,58 +77,55 @@ impl Node for Node4 {
             Upgrade
         }
     }
+}
 
-    fn get_child(&self, index: usize) -> Option<&Box<dyn Node>> {
+impl<L> Children<L> for Node4<L> {
+    fn get_child(&self, index: usize) -> Option<&L> {
         self.children[index].as_ref()
     }
 
-    fn exists_add(&mut self, index: &KeyChildIndex, rest: &[u8]) -> NodeOption {
-        //if None create Node0 and add rest, if Some add content
-        let upgraded_node = self.children[index.child]
-            .get_or_insert_with(|| Box::new(Node0::new()))
-            .as_mut()
-            .add(rest);
-        if upgraded_node.is_some() {
-            self.children[index.child] = upgraded_node;
-        }
-        None
+    fn take_child(&mut self, index: usize) -> Option<L> {
+        self.children[index].take()
     }
 
-    fn insert_add(&mut self, index: &KeyChildIndex, first: u8, rest: &[u8]) -> NodeOption {
+    fn set_child(&mut self, index: usize, child: L) {
+        self.children[index] = Some(child);
+    }
+
+    fn insert_child(&mut self, index: &KeyChildIndex, key: u8, child: L) {
         //add value to existing Node4 if there is room
-        self.keys[index.key] = Some(first);
-        let mut new_node = Node0::new();
-        self.children[index.child] = new_node.add(rest).or_else(|| Some(Box::new(new_node)));
+        self.keys[index.key] = Some(key);
+        self.children[index.child] = Some(child);
         self.size += 1;
-        None
     }
 
-    fn upgrade_add(&mut self, values: &[u8]) -> NodeOption {
-        //expand to node16 and then add new value
-        let mut upgraded_node = Node16::from(self);
-        upgraded_node.add(values);
-        Some(Box::new(upgraded_node))
+    fn remove_child(&mut self, index: &KeyChildIndex) -> Option<L> {
+        //the last key fills the gap so the first size entries stay occupied
+        self.size -= 1;
+        self.keys.swap(index.key, self.size);
+        self.keys[self.size] = None;
+        self.children.swap(index.child, self.size);
+        self.children[self.size].take()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::trie::nodes::node::NodeType;
+    use crate::trie::nodes::store::{Heap, Store};
 
     #[test]
     fn test_adding_words_to_node4() {
-        let mut node = NodeOption::Some(Box::new(Node0::new()));
+        let mut node = Heap.leaf();
         for i in 0..=3 {
-            let upgrade = node.as_mut().unwrap().add(&[0, i]);
-            if upgrade.is_some() {
-                node = upgrade;
-            }
+            Heap.add(&mut node, &[0, i]);
         }
-        if let Some(root) = node {
-            let child = root.as_any().downcast_ref::<Node4>().unwrap().children[0]
-                .as_ref()
-                .unwrap();
-            assert!(child.is_full());
+        if let NodeType::N4(root) = node {
+            let child = root.children[0].as_ref().unwrap();
+            assert!(matches!(child, NodeType::N4(n) if n.is_full()));
+        } else {
+            panic!()
         }
     }
 }
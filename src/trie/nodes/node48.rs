@@ -1,25 +1,24 @@
-use crate::trie::nodes::node::{KeyChildIndex, Node, NodeLocation, NodeOption};
-use crate::trie::nodes::{node0::Node0, node16::Node16, node256::Node256};
+use crate::trie::nodes::node::{Children, KeyChildIndex, Node, NodeLocation};
+use crate::trie::nodes::node16::Node16;
 use arr_macro::arr;
-use std::any::Any;
 use crate::trie::nodes::node::NodeLocation::{Exists, Insert, Upgrade};
 
-#[derive(Debug)]
-pub struct Node48 {
+#[derive(Debug, Clone)]
+pub struct Node48<L> {
     pub(crate) keys: [Option<u8>; 256],
     //index represents value, and value represents index in children
-    pub(crate) children: [NodeOption; 48],
+    pub(crate) children: [Option<L>; 48],
     pub(crate) size: usize,
     pub(crate) terminal: bool,
 }
 
-impl Default for Node48 {
+impl<L> Default for Node48<L> {
     fn default() -> Self {
         Node48::new()
     }
 }
 
-impl Node48 {
+impl<L> Node48<L> {
     pub fn new() -> Self {
         Node48 {
             keys: [None; 256],
@@ -29,13 +28,13 @@ impl Node48 {
         }
     }
 
-    pub fn from(node: &mut Node16) -> Self {
+    pub fn from(node: &mut Node16<L>) -> Self {
         //add keys which point to appropriate child index
         let mut new_node = Node48::new();
         //index in within keys represents the u8 and its value represents the index in children
         for i in 0..node.size as u8 {
             let index = i as usize;
-            new_node.keys[node.keys[index].unwrap() as usize] = Some(i);
+            new_node.keys[node.keys[index] as usize] = Some(i);
             new_node.children[index] = node.children[index].take();
         }
 
@@ -45,7 +44,7 @@ impl Node48 {
     }
 }
 
-impl Node for Node48 {
+impl<L> Node for Node48<L> {
     fn is_full(&self) -> bool {
         self.size == self.children.len()
     }
@@ -62,10 +61,6 @@ impl Node for Node48 {
         self.terminal = terminal
     }
 
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-
     fn get_index(&self, value: u8) -> NodeLocation {
         let cur_value_index = value as usize;
         if let Some(key_value) = self.keys[cur_value_index] {
@@ -76,56 +71,56 @@ impl Node for Node48 {
             Upgrade
         }
     }
+}
 
-    fn get_child(&self, index: usize) -> Option<&Box<dyn Node>> {
+impl<L> Children<L> for Node48<L> {
+    fn get_child(&self, index: usize) -> Option<&L> {
         self.children[index].as_ref()
     }
 
-    fn exists_add(&mut self, index: &KeyChildIndex, rest: &[u8]) -> NodeOption {
-        let upgraded_node = self.children[index.child]
-            .get_or_insert_with(|| Box::new(Node0::new()))
-            .as_mut()
-            .add(rest);
-        if upgraded_node.is_some() {
-            self.children[index.child] = upgraded_node;
-        }
-        None
+    fn take_child(&mut self, index: usize) -> Option<L> {
+        self.children[index].take()
     }
 
-    fn insert_add(&mut self, index: &KeyChildIndex, first: u8, rest: &[u8]) -> NodeOption {
+    fn set_child(&mut self, index: usize, child: L) {
+        self.children[index] = Some(child);
+    }
+
+    fn insert_child(&mut self, index: &KeyChildIndex, _key: u8, child: L) {
         self.keys[index.key] = Some(self.size as u8); //FIXME this is the same as index.child
-        let mut new_node = Node0::new();
-        self.children[index.child] = new_node.add(rest).or_else(|| Some(Box::new(new_node)));
+        self.children[index.child] = Some(child);
 
         self.size += 1;
-        None
     }
 
-    fn upgrade_add(&mut self, values: &[u8]) -> NodeOption {
-        let mut upgraded_node = Node256::from(self);
-        upgraded_node.add(values);
-        Some(Box::new(upgraded_node))
+    fn remove_child(&mut self, index: &KeyChildIndex) -> Option<L> {
+        //the last child moves into the freed slot so the first size children stay occupied
+        self.keys[index.key] = None;
+        self.size -= 1;
+        if index.child != self.size {
+            let moved = self.keys.iter().position(|&k| k == Some(self.size as u8)).unwrap();
+            self.keys[moved] = Some(index.child as u8);
+            self.children.swap(index.child, self.size);
+        }
+        self.children[self.size].take()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-    use crate::trie::nodes::node4::Node4;
+    use crate::trie::nodes::node::NodeType;
+    use crate::trie::nodes::store::{Heap, Store};
+    use std::sync::Arc;
 
     #[test]
     fn order_preserved_48_exact_match() {
-        let mut node = NodeOption::Some(Box::new(Node4::new()));
+        let mut node = NodeType::N4(Arc::default());
 
         for i in 0..48 {
-            let upgrade = node.as_mut().unwrap().add(&[i * 2]);
-            if upgrade.is_some() {
-                node = upgrade;
-            }
+            Heap.add(&mut node, &[i * 2]);
         }
 
-        if let Some(n) = node {
-            let node48 = n.as_any().downcast_ref::<Node48>().unwrap();
+        if let NodeType::N48(node48) = node {
             for (i, &k) in node48.keys.iter().enumerate() {
                 if i < 96 {
                     //only first entries 48 considered
@@ -135,7 +130,7 @@ mod tests {
                         }
                         Some(c) => {
                             assert_eq!(i % 2, 0);
-                            assert!(matches!(&node48.children[c as usize], Some(_)));
+                            assert!(node48.children[c as usize].is_some());
                         }
                     }
                 }
@@ -144,4 +139,26 @@ mod tests {
             panic!()
         }
     }
+
+    #[test]
+    fn removed_slots_are_refilled_by_the_last_child() {
+        let mut node = NodeType::N4(Arc::default());
+        for i in 0..40 {
+            Heap.add(&mut node, &[i, i]);
+        }
+        for i in (0..40).step_by(3) {
+            Heap.remove(&mut node, &[i, i]);
+        }
+
+        if let NodeType::N48(node48) = &node {
+            assert_eq!(node48.size, 26);
+            assert!(node48.children[..26].iter().all(|child| child.is_some()));
+            assert!(node48.children[26..].iter().all(|child| child.is_none()));
+        } else {
+            panic!()
+        }
+        for i in 0..40 {
+            assert_eq!(Heap.exists(&node, &[i, i]), i % 3 != 0);
+        }
+    }
 }
@@ -0,0 +1,50 @@
+use crate::trie::nodes::node::NodeRef;
+use crate::trie::nodes::store::Store;
+
+//distinct ways to continue segment below node up to and including the next separator,
+//each with the number of values under it when counted, in byte order
+//a value ending before the next separator is a segment of its own with a count of one
+//only the segment bytes are ever built, the values below a separator are counted not collected
+//without counting only the nodes up to the next separator are visited, counting visits every
+//node below node
+pub fn segments<S: Store>(
+    store: &S,
+    node: NodeRef<S::Link>,
+    segment: Vec<u8>,
+    separator: &[u8],
+    counted: bool,
+) -> Vec<(Vec<u8>, usize)> {
+    let mut found = Vec::new();
+    let mut pending = vec![(node, segment)];
+    while let Some((node, segment)) = pending.pop() {
+        if !segment.is_empty() && node.is_terminal() {
+            found.push((segment.clone(), 1));
+        }
+        for (key, child) in node.children().into_iter().rev() {
+            let mut child_segment = segment.clone();
+            child_segment.push(key);
+            let child = store.get(child);
+            if child_segment.ends_with(separator) {
+                let values = if counted { count(store, child) } else { 0 };
+                found.push((child_segment, values));
+            } else {
+                pending.push((child, child_segment));
+            }
+        }
+    }
+    //segments ending in a separator are recorded when their parent is visited, ahead of
+    //shorter ones still on the stack
+    found.sort_unstable();
+    found
+}
+
+//values below and including node
+fn count<S: Store>(store: &S, node: NodeRef<S::Link>) -> usize {
+    let mut values = 0;
+    let mut pending = vec![node];
+    while let Some(node) = pending.pop() {
+        values += node.is_terminal() as usize;
+        pending.extend(node.children().into_iter().map(|(_, child)| store.get(child)));
+    }
+    values
+}
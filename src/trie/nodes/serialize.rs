@@ -0,0 +1,101 @@
+use crate::trie::nodes::node::NodeLocation::Insert;
+use crate::trie::nodes::node::{Children, Node, NodeRef};
+use crate::trie::nodes::store::Store;
+use std::io::{self, Read, Write};
+
+//longest key read accepts, so the nesting of a loaded file is bounded
+//a few bytes of input per level would otherwise let a crafted file build a chain deep enough to
+//overflow the stack when the nodes are dropped, which happens one level per call
+//write has no limit, a tree holding a longer key is saved but can't be loaded back
+pub const MAX_DEPTH: usize = 4096;
+
+//nodes are written depth first, each as
+//  terminal flag: u8
+//  child count: u16 little endian
+//  per child in key order: key byte then the child node
+//the layout doesn't depend on node types so any store can read what another wrote
+//both directions keep their own stack so deep trees don't use up the thread's
+pub fn write<S: Store, W: Write>(store: &S, root: NodeRef<S::Link>, out: &mut W) -> io::Result<()> {
+    let mut pending = vec![(None, root)];
+    while let Some((key, node)) = pending.pop() {
+        if let Some(key) = key {
+            out.write_all(&[key])?;
+        }
+        let children = node.children();
+        out.write_all(&[node.is_terminal() as u8])?;
+        out.write_all(&(children.len() as u16).to_le_bytes())?;
+        for (key, child) in children.into_iter().rev() {
+            pending.push((Some(key), store.get(child)));
+        }
+    }
+    Ok(())
+}
+
+//node whose children are still being read
+struct Partial<L> {
+    terminal: bool,
+    count: usize,
+    children: Vec<(u8, L)>,
+}
+
+fn header<L, R: Read>(input: &mut R) -> io::Result<Partial<L>> {
+    let mut header = [0; 3];
+    input.read_exact(&mut header)?;
+    let terminal = match header[0] {
+        0 => false,
+        1 => true,
+        _ => return Err(invalid("terminal flag must be 0 or 1")),
+    };
+    let count = u16::from_le_bytes([header[1], header[2]]) as usize;
+    if count > 256 {
+        return Err(invalid("node has more than 256 children"));
+    }
+    Ok(Partial { terminal, count, children: Vec::with_capacity(count) })
+}
+
+//nodes are created at their final size as in a bulk load, once all their children are read
+//a leaf below the root that isn't terminal is refused, it would break what validate checks
+pub fn read<S: Store, R: Read>(store: &mut S, input: &mut R) -> io::Result<S::Link> {
+    //ancestors of node, each with the key leading to the next one down
+    let mut ancestors: Vec<(Partial<S::Link>, u8)> = Vec::new();
+    let mut node = header(input)?;
+    loop {
+        if node.children.len() < node.count {
+            let mut key = [0];
+            input.read_exact(&mut key)?;
+            if node.children.last().is_some_and(|(last, _)| *last >= key[0]) {
+                return Err(invalid("child keys must be strictly increasing"));
+            }
+            if ancestors.len() == MAX_DEPTH {
+                return Err(invalid("nodes are nested deeper than the longest key allowed"));
+            }
+            ancestors.push((node, key[0]));
+            node = header(input)?;
+            if node.count == 0 && !node.terminal {
+                return Err(invalid("leaf is neither terminal nor the root"));
+            }
+            continue;
+        }
+
+        let mut link = store.with_capacity(node.children.len());
+        let mut built = store.get_mut(&mut link);
+        built.set_terminal(node.terminal);
+        for (key, child) in node.children {
+            match built.get_index(key) {
+                Insert(index) => built.insert_child(&index, key, child),
+                _ => unreachable!(),
+            }
+        }
+        match ancestors.pop() {
+            Some((parent, key)) => {
+                node = parent;
+                node.children.push((key, link));
+            }
+            None => return Ok(link),
+        }
+    }
+}
+
+pub fn invalid(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason)
+}
@@ -0,0 +1,182 @@
+use crate::trie::nodes::node::NodeLocation::{Exists, Insert, Upgrade};
+use crate::trie::nodes::node::{Children, Node, NodeMut, NodeRef, NodeType};
+use crate::trie::nodes::{
+    node0::Node0, node16::Node16, node256::Node256, node4::Node4, node48::Node48,
+};
+use std::sync::Arc;
+
+//owns the memory behind nodes, nodes only refer to their children through Link
+pub trait Store {
+    type Link;
+
+    fn get<'a>(&'a self, link: &'a Self::Link) -> NodeRef<'a, Self::Link>;
+    fn get_mut<'a>(&'a mut self, link: &'a mut Self::Link) -> NodeMut<'a, Self::Link>;
+    fn leaf(&mut self) -> Self::Link;
+    //empty node of the smallest type able to hold the given number of children
+    fn with_capacity(&mut self, children: usize) -> Self::Link;
+    //replace a full node with the next larger node type holding the same children
+    fn upgrade(&mut self, link: &mut Self::Link);
+    //free a node that is no longer linked from anywhere, its children are left alone
+    fn release(&mut self, link: Self::Link);
+    //take over the nodes of another store, links into it are rewritten to stay valid
+    fn absorb(&mut self, other: Self, links: &mut [Self::Link])
+    where
+        Self: Sized;
+
+    fn add(&mut self, link: &mut Self::Link, values: &[u8]) {
+        if let Some((first, rest)) = values.split_first() {
+            match self.get(link).get_index(*first) {
+                Exists(index) => {
+                    //children are taken out while they are modified so the store can be borrowed again
+                    let mut child = self
+                        .get_mut(link)
+                        .take_child(index.child)
+                        .unwrap_or_else(|| self.leaf());
+                    self.add(&mut child, rest);
+                    self.get_mut(link).set_child(index.child, child);
+                }
+                Insert(index) => {
+                    let mut child = self.leaf();
+                    self.add(&mut child, rest);
+                    self.get_mut(link).insert_child(&index, *first, child);
+                }
+                Upgrade => {
+                    self.upgrade(link);
+                    self.add(link, values);
+                }
+            }
+        } else {
+            self.get_mut(link).set_terminal(true);
+        }
+    }
+
+    //unmark the value and drop the nodes that only led to it, returns whether it was there
+    //the value is looked up read only first, get_mut copies shared heap nodes so nothing is
+    //touched when it isn't there
+    fn remove(&mut self, link: &mut Self::Link, values: &[u8]) -> bool {
+        if !self.exists(link, values) {
+            return false;
+        }
+        self.unlink(link, values);
+        true
+    }
+
+    //remove for a value known to be below link
+    fn unlink(&mut self, link: &mut Self::Link, values: &[u8]) {
+        match values.split_first() {
+            Some((first, rest)) => {
+                let index = match self.get(link).get_index(*first) {
+                    Exists(index) => index,
+                    _ => unreachable!("unlink is only given values that exist"),
+                };
+                let mut child = self.get_mut(link).take_child(index.child).unwrap();
+                self.unlink(&mut child, rest);
+                let unused = self.get(&child);
+                if unused.is_empty() && !unused.is_terminal() {
+                    self.get_mut(link).remove_child(&index);
+                    self.release(child);
+                } else {
+                    self.get_mut(link).set_child(index.child, child);
+                }
+            }
+            None => self.get_mut(link).set_terminal(false),
+        }
+    }
+
+    fn exists(&self, link: &Self::Link, values: &[u8]) -> bool {
+        self.find(link, values).is_some_and(|node| node.is_terminal())
+    }
+
+    //calls found with the length of every stored prefix of values, shortest first
+    //one descent through get_index, stopping where values leave the tree
+    fn prefixes(&self, link: &Self::Link, values: &[u8], mut found: impl FnMut(usize)) {
+        let mut node = self.get(link);
+        for (depth, value) in values.iter().enumerate() {
+            if node.is_terminal() {
+                found(depth);
+            }
+            node = match node.get_index(*value) {
+                Exists(index) => match node.get_child(index.child) {
+                    Some(child) => self.get(child),
+                    None => return,
+                },
+                _ => return,
+            };
+        }
+        if node.is_terminal() {
+            found(values.len());
+        }
+    }
+
+    //node reached by following values from link
+    //walk iteratively so each level is a single match rather than a recursive call
+    fn find<'a>(&'a self, link: &'a Self::Link, values: &[u8]) -> Option<NodeRef<'a, Self::Link>> {
+        let mut node = self.get(link);
+        for value in values {
+            node = match node.get_index(*value) {
+                Exists(index) => self.get(node.get_child(index.child)?),
+                _ => return None,
+            };
+        }
+        Some(node)
+    }
+}
+
+//every node is its own heap allocation, links share ownership of the node they point to
+#[derive(Debug, Default)]
+pub struct Heap;
+
+impl Store for Heap {
+    type Link = NodeType;
+
+    #[inline]
+    fn get<'a>(&'a self, link: &'a NodeType) -> NodeRef<'a, NodeType> {
+        match link {
+            NodeType::N0(n) => NodeRef::N0(n),
+            NodeType::N4(n) => NodeRef::N4(n),
+            NodeType::N16(n) => NodeRef::N16(n),
+            NodeType::N48(n) => NodeRef::N48(n),
+            NodeType::N256(n) => NodeRef::N256(n),
+        }
+    }
+
+    #[inline]
+    fn get_mut<'a>(&'a mut self, link: &'a mut NodeType) -> NodeMut<'a, NodeType> {
+        match link {
+            NodeType::N0(n) => NodeMut::N0(n),
+            NodeType::N4(n) => NodeMut::N4(Arc::make_mut(n)),
+            NodeType::N16(n) => NodeMut::N16(Arc::make_mut(n)),
+            NodeType::N48(n) => NodeMut::N48(Arc::make_mut(n)),
+            NodeType::N256(n) => NodeMut::N256(Arc::make_mut(n)),
+        }
+    }
+
+    fn leaf(&mut self) -> NodeType {
+        NodeType::N0(Node0::new())
+    }
+
+    fn with_capacity(&mut self, children: usize) -> NodeType {
+        match children {
+            0 => self.leaf(),
+            1..=4 => NodeType::N4(Arc::default()),
+            5..=16 => NodeType::N16(Arc::default()),
+            17..=48 => NodeType::N48(Arc::default()),
+            _ => NodeType::N256(Arc::default()),
+        }
+    }
+
+    fn upgrade(&mut self, link: &mut NodeType) {
+        *link = match link {
+            NodeType::N0(n) => NodeType::N4(Arc::new(Node4::from(n))),
+            NodeType::N4(n) => NodeType::N16(Arc::new(Node16::from(Arc::make_mut(n)))),
+            NodeType::N16(n) => NodeType::N48(Arc::new(Node48::from(Arc::make_mut(n)))),
+            NodeType::N48(n) => NodeType::N256(Arc::new(Node256::from(Arc::make_mut(n)))),
+            NodeType::N256(_) => unreachable!("Node256 does not grow"),
+        }
+    }
+
+    fn release(&mut self, _link: NodeType) {}
+
+    //links own their nodes so there is nothing to move
+    fn absorb(&mut self, _other: Heap, _links: &mut [NodeType]) {}
+}
@@ -0,0 +1,210 @@
+use crate::trie::errors::{InvalidNode, Violation};
+use crate::trie::nodes::node::{NodeLocation, NodeRef};
+use crate::trie::nodes::store::Store;
+use std::ptr;
+
+//every broken invariant in the tree under root, parents before their children
+pub fn validate<S: Store>(store: &S, root: NodeRef<S::Link>) -> Vec<InvalidNode> {
+    let mut invalid = Vec::new();
+    let mut pending = vec![(root, Vec::new())];
+    while let Some((node, path)) = pending.pop() {
+        for violation in check(node, !path.is_empty()) {
+            invalid.push(InvalidNode {
+                path: path.clone(),
+                kind: node.kind(),
+                violation,
+            });
+        }
+        for (key, child) in node.children().into_iter().rev() {
+            let mut child_path = path.clone();
+            child_path.push(key);
+            pending.push((store.get(child), child_path));
+        }
+    }
+    invalid
+}
+
+fn check<L>(node: NodeRef<L>, below_root: bool) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    match node {
+        NodeRef::N0(_) => {}
+        NodeRef::N4(n) => {
+            slots(n.size, n.children.iter().map(Option::is_some), &mut violations);
+            for (slot, (key, child)) in n.keys.iter().zip(n.children.iter()).enumerate() {
+                if key.is_some() != child.is_some() {
+                    violations.push(Violation::KeyWithoutChild { slot });
+                }
+                if key.is_some() && n.keys[..slot].contains(key) {
+                    violations.push(Violation::Duplicate { key: key.unwrap() });
+                }
+            }
+        }
+        NodeRef::N16(n) => {
+            slots(n.size, n.children.iter().map(Option::is_some), &mut violations);
+            for slot in 1..n.size.min(n.keys.len()) {
+                if n.keys[slot - 1] >= n.keys[slot] {
+                    violations.push(Violation::Unsorted { slot });
+                }
+            }
+        }
+        NodeRef::N48(n) => {
+            slots(n.size, n.children.iter().map(Option::is_some), &mut violations);
+            let mut referenced = [false; 48];
+            for (key, index) in (0..=255u8).zip(n.keys.iter()) {
+                if let Some(index) = *index {
+                    match referenced.get_mut(index as usize) {
+                        Some(_) if index as usize >= n.size => {
+                            violations.push(Violation::IndexOutOfRange { key, index })
+                        }
+                        Some(true) => violations.push(Violation::DuplicateIndex { key, index }),
+                        Some(seen) => *seen = true,
+                        None => violations.push(Violation::IndexOutOfRange { key, index }),
+                    }
+                }
+            }
+            for (slot, seen) in referenced.iter().enumerate().take(n.size) {
+                if !seen {
+                    violations.push(Violation::Unreferenced { slot });
+                }
+            }
+        }
+        //children sit at their byte so only the size can be off
+        NodeRef::N256(n) => {
+            let children = n.children.iter().filter(|child| child.is_some()).count();
+            if children != n.size {
+                violations.push(Violation::Size { size: n.size, children });
+            }
+        }
+    }
+
+    //whatever the layout, looking a key up has to lead to the child listed under it
+    let children = node.children();
+    for (key, child) in &children {
+        let found = match node.get_index(*key) {
+            NodeLocation::Exists(index) => node.get_child(index.child),
+            _ => None,
+        };
+        if !found.is_some_and(|found| ptr::eq(found, *child)) {
+            violations.push(Violation::Misplaced { key: *key });
+        }
+    }
+    //removal prunes nodes that no longer lead to a value, only an empty tree's root is left bare
+    if below_root && children.is_empty() && !node.is_terminal() {
+        violations.push(Violation::EmptyLeaf);
+    }
+    violations
+}
+
+//slots before size are in use and none after it
+fn slots(size: usize, occupied: impl Iterator<Item = bool>, violations: &mut Vec<Violation>) {
+    let mut children = 0;
+    for (slot, occupied) in occupied.enumerate() {
+        children += occupied as usize;
+        if occupied != (slot < size) {
+            violations.push(Violation::Slot { slot });
+        }
+    }
+    if children != size {
+        violations.push(Violation::Size { size, children });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::validate;
+    use crate::trie::enums::NodeKind;
+    use crate::trie::errors::Violation;
+    use crate::trie::nodes::node::NodeType;
+    use crate::trie::nodes::store::{Heap, Store};
+    use std::sync::Arc;
+
+    fn violations(root: &NodeType) -> Vec<(Vec<u8>, NodeKind, Violation)> {
+        validate(&Heap, Heap.get(root))
+            .into_iter()
+            .map(|invalid| (invalid.path, invalid.kind, invalid.violation))
+            .collect()
+    }
+
+    //root with the given number of single byte children
+    fn root(children: u8) -> NodeType {
+        let mut root = Heap.leaf();
+        for key in 0..children {
+            Heap.add(&mut root, &[key * 2]);
+        }
+        root
+    }
+
+    #[test]
+    fn trees_built_by_adds_are_valid() {
+        for children in [0, 3, 10, 40, 128] {
+            assert_eq!(violations(&root(children)), vec![]);
+        }
+    }
+
+    #[test]
+    fn reports_broken_node4_and_node16_layouts() {
+        let mut node4 = root(3);
+        if let NodeType::N4(n) = &mut node4 {
+            let n = Arc::get_mut(n).unwrap();
+            n.keys[1] = n.keys[0];
+            n.size = 2;
+        }
+        assert_eq!(
+            violations(&node4),
+            vec![
+                (vec![], NodeKind::Node4, Violation::Slot { slot: 2 }),
+                (vec![], NodeKind::Node4, Violation::Size { size: 2, children: 3 }),
+                (vec![], NodeKind::Node4, Violation::Duplicate { key: 0 }),
+                (vec![], NodeKind::Node4, Violation::Misplaced { key: 0 }),
+            ]
+        );
+
+        let mut node16 = root(10);
+        if let NodeType::N16(n) = &mut node16 {
+            Arc::get_mut(n).unwrap().keys.swap(3, 4);
+        }
+        assert!(violations(&node16).contains(&(vec![], NodeKind::Node16, Violation::Unsorted { slot: 4 })));
+    }
+
+    #[test]
+    fn reports_broken_node48_and_node256_layouts() {
+        let mut node48 = root(40);
+        if let NodeType::N48(n) = &mut node48 {
+            let n = Arc::get_mut(n).unwrap();
+            n.keys[2] = Some(0);
+            n.keys[4] = Some(45);
+        }
+        assert_eq!(
+            violations(&node48),
+            vec![
+                (vec![], NodeKind::Node48, Violation::DuplicateIndex { key: 2, index: 0 }),
+                (vec![], NodeKind::Node48, Violation::IndexOutOfRange { key: 4, index: 45 }),
+                (vec![], NodeKind::Node48, Violation::Unreferenced { slot: 1 }),
+                (vec![], NodeKind::Node48, Violation::Unreferenced { slot: 2 }),
+            ]
+        );
+
+        let mut node256 = root(128);
+        if let NodeType::N256(n) = &mut node256 {
+            Arc::get_mut(n).unwrap().size = 3;
+        }
+        assert_eq!(
+            violations(&node256),
+            vec![(vec![], NodeKind::Node256, Violation::Size { size: 3, children: 128 })]
+        );
+    }
+
+    #[test]
+    fn reports_empty_leaves_below_the_root() {
+        let mut root = Heap.leaf();
+        Heap.add(&mut root, b"ab");
+        Heap.add(&mut root, b"c");
+        if let NodeType::N4(n) = &mut root {
+            Arc::get_mut(n).unwrap().children[1] = Some(Heap.leaf());
+        }
+        assert_eq!(violations(&root), vec![(b"c".to_vec(), NodeKind::Node0, Violation::EmptyLeaf)]);
+
+        //an empty tree's root is fine
+        assert_eq!(violations(&Heap.leaf()), vec![]);
+    }
+}
@@ -0,0 +1,375 @@
+use std::hint;
+use std::ptr;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+use std::sync::atomic::{fence, AtomicBool, AtomicPtr, AtomicU16, AtomicU64, AtomicU8};
+
+//version layout: bit 0 obsolete, bit 1 locked, the rest counts completed writes
+const OBSOLETE: u64 = 0b01;
+const LOCKED: u64 = 0b10;
+
+//a node changed underneath the caller, the operation has to start over from the root
+#[derive(Debug)]
+pub struct Restart;
+
+//node shared between threads, used by both optimistic lock coupling and ROWEX
+//every field is atomic and writers publish a child before the key and size that lead to it,
+//so a reader without any lock always sees some consistent state of the node
+//removing a child only nulls its pointer, the key keeps its slot until the node grows
+//replaced nodes are freed through epoch reclamation, readers stay pinned while they hold pointers
+pub struct VersionedNode {
+    version: AtomicU64,
+    terminal: AtomicBool,
+    size: AtomicU16,
+    body: Body,
+}
+
+//same node types as the single threaded tree, the body is fixed once the node is created
+enum Body {
+    N0,
+    N4(Box<Keyed<4>>),
+    N16(Box<Keyed<16>>),
+    N48(Box<Indexed>),
+    N256(Box<Direct>),
+}
+
+//keys are kept in insertion order and scanned linearly
+struct Keyed<const N: usize> {
+    keys: [AtomicU8; N],
+    children: [AtomicPtr<VersionedNode>; N],
+}
+
+struct Indexed {
+    //slot in children plus one, zero means no child
+    index: [AtomicU8; 256],
+    children: [AtomicPtr<VersionedNode>; 48],
+}
+
+struct Direct {
+    children: [AtomicPtr<VersionedNode>; 256],
+}
+
+fn null_children<const N: usize>() -> [AtomicPtr<VersionedNode>; N] {
+    std::array::from_fn(|_| AtomicPtr::new(ptr::null_mut()))
+}
+
+impl<const N: usize> Keyed<N> {
+    fn new() -> Box<Self> {
+        Box::new(Keyed {
+            keys: std::array::from_fn(|_| AtomicU8::new(0)),
+            children: null_children(),
+        })
+    }
+
+    fn find(&self, size: usize, key: u8) -> Option<&AtomicPtr<VersionedNode>> {
+        //keys past size may be half written but are never looked at
+        (0..size.min(N))
+            .find(|&i| self.keys[i].load(Relaxed) == key)
+            .map(|i| &self.children[i])
+    }
+
+    //slots left behind by removed children are dropped, returns how many were copied
+    fn copy_to<const M: usize>(&self, size: usize, other: &Keyed<M>) -> usize {
+        let mut copied = 0;
+        for i in 0..size {
+            let child = self.children[i].load(Relaxed);
+            if !child.is_null() {
+                other.keys[copied].store(self.keys[i].load(Relaxed), Relaxed);
+                other.children[copied].store(child, Relaxed);
+                copied += 1;
+            }
+        }
+        copied
+    }
+}
+
+impl VersionedNode {
+    fn with_body(body: Body) -> Box<Self> {
+        Box::new(VersionedNode {
+            version: AtomicU64::new(0),
+            terminal: AtomicBool::new(false),
+            size: AtomicU16::new(0),
+            body,
+        })
+    }
+
+    pub fn leaf() -> Box<Self> {
+        VersionedNode::with_body(Body::N0)
+    }
+
+    pub fn node256() -> Box<Self> {
+        VersionedNode::with_body(Body::N256(Box::new(Direct { children: null_children() })))
+    }
+
+    //private path for the rest of a key, built before it is published so it needs no locking
+    pub fn chain(rest: &[u8]) -> *mut VersionedNode {
+        let leaf = VersionedNode::leaf();
+        leaf.terminal.store(true, Relaxed);
+        rest.iter().rev().fold(Box::into_raw(leaf), |child, &key| {
+            let node = VersionedNode::with_body(Body::N4(Keyed::new()));
+            node.insert_child(key, child);
+            Box::into_raw(node)
+        })
+    }
+
+    //wait out any writer and return the version to validate against later
+    pub fn read_lock(&self) -> Result<u64, Restart> {
+        loop {
+            let version = self.version.load(Acquire);
+            if version & LOCKED != 0 {
+                hint::spin_loop();
+            } else if version & OBSOLETE != 0 {
+                return Err(Restart);
+            } else {
+                return Ok(version);
+            }
+        }
+    }
+
+    //everything read since read_lock is only valid if no writer got in between
+    pub fn check(&self, version: u64) -> Result<(), Restart> {
+        fence(Acquire);
+        if self.version.load(Relaxed) == version {
+            Ok(())
+        } else {
+            Err(Restart)
+        }
+    }
+
+    //write lock without having read the node first, fails once the node has been replaced
+    pub fn lock(&self) -> Result<(), Restart> {
+        loop {
+            let version = self.read_lock()?;
+            if self.upgrade_lock(version).is_ok() {
+                return Ok(());
+            }
+        }
+    }
+
+    //turn a read into a write lock, fails if anyone wrote since the version was read
+    pub fn upgrade_lock(&self, version: u64) -> Result<(), Restart> {
+        self.version
+            .compare_exchange(version, version + LOCKED, Acquire, Relaxed)
+            .map_err(|_| Restart)?;
+        //keep the locked version ahead of the writes that follow
+        fence(Release);
+        Ok(())
+    }
+
+    pub fn unlock(&self) {
+        self.version.fetch_add(LOCKED, Release);
+    }
+
+    //clears the lock and marks the node as replaced in the same step
+    pub fn unlock_obsolete(&self) {
+        self.version.fetch_add(LOCKED | OBSOLETE, Release);
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        self.terminal.load(Acquire)
+    }
+
+    pub fn set_terminal(&self, terminal: bool) {
+        self.terminal.store(terminal, Release)
+    }
+
+    pub fn is_full(&self) -> bool {
+        let size = self.size.load(Relaxed) as usize;
+        match &self.body {
+            Body::N0 => true,
+            Body::N4(_) => size == 4,
+            Body::N16(_) => size == 16,
+            Body::N48(_) => size == 48,
+            Body::N256(_) => size == 256,
+        }
+    }
+
+    fn slot(&self, key: u8) -> Option<&AtomicPtr<VersionedNode>> {
+        let size = self.size.load(Acquire) as usize;
+        match &self.body {
+            Body::N0 => None,
+            Body::N4(n) => n.find(size, key),
+            Body::N16(n) => n.find(size, key),
+            Body::N48(n) => match n.index[key as usize].load(Acquire) {
+                0 => None,
+                slot => n.children.get(slot as usize - 1),
+            },
+            Body::N256(n) => Some(&n.children[key as usize]),
+        }
+    }
+
+    //null when there is no child
+    pub fn find_child(&self, key: u8) -> *mut VersionedNode {
+        self.slot(key)
+            .map_or(ptr::null_mut(), |child| child.load(Acquire))
+    }
+
+    //whether the key can be added without growing, because there is room or its old slot is left
+    pub fn has_room(&self, key: u8) -> bool {
+        !self.is_full() || self.slot(key).is_some()
+    }
+
+    //caller holds the write lock and has checked the node has room
+    //the child is in place before anything that leads to it becomes visible
+    pub fn insert_child(&self, key: u8, child: *mut VersionedNode) {
+        //every key has a slot in a Node256, in the others one is left when a child was removed
+        if let Some(slot) = self.slot(key) {
+            slot.store(child, Release);
+            return;
+        }
+        let size = self.size.load(Relaxed) as usize;
+        match &self.body {
+            Body::N0 => unreachable!(),
+            Body::N4(n) => {
+                n.children[size].store(child, Release);
+                n.keys[size].store(key, Relaxed);
+            }
+            Body::N16(n) => {
+                n.children[size].store(child, Release);
+                n.keys[size].store(key, Relaxed);
+            }
+            Body::N48(n) => {
+                n.children[size].store(child, Release);
+                n.index[key as usize].store(size as u8 + 1, Release);
+            }
+            Body::N256(_) => unreachable!("every key has a slot in a Node256"),
+        }
+        self.size.store(size as u16 + 1, Release);
+    }
+
+    //caller holds the write lock, readers already past the key may still follow the old child
+    pub fn remove_child(&self, key: u8) {
+        if let Some(slot) = self.slot(key) {
+            slot.store(ptr::null_mut(), Release);
+        }
+    }
+
+    //caller holds the write lock and the key already has a child
+    pub fn replace_child(&self, key: u8, child: *mut VersionedNode) {
+        self.slot(key).unwrap().store(child, Release);
+    }
+
+    //copy into the next larger node type, caller holds the write lock on self
+    //slots of removed children aren't copied
+    pub fn grow(&self) -> Box<VersionedNode> {
+        let size = self.size.load(Relaxed) as usize;
+        let (body, size) = match &self.body {
+            Body::N0 => (Body::N4(Keyed::new()), 0),
+            Body::N4(n) => {
+                let grown = Keyed::new();
+                let size = n.copy_to(size, &grown);
+                (Body::N16(grown), size)
+            }
+            Body::N16(n) => {
+                let grown = Box::new(Indexed {
+                    index: std::array::from_fn(|_| AtomicU8::new(0)),
+                    children: null_children(),
+                });
+                let mut copied = 0;
+                for i in 0..size {
+                    let child = n.children[i].load(Relaxed);
+                    if !child.is_null() {
+                        grown.index[n.keys[i].load(Relaxed) as usize].store(copied as u8 + 1, Relaxed);
+                        grown.children[copied].store(child, Relaxed);
+                        copied += 1;
+                    }
+                }
+                (Body::N48(grown), copied)
+            }
+            Body::N48(n) => {
+                let grown = Box::new(Direct { children: null_children() });
+                for (key, slot) in n.index.iter().enumerate() {
+                    match slot.load(Relaxed) {
+                        0 => {}
+                        slot => grown.children[key]
+                            .store(n.children[slot as usize - 1].load(Relaxed), Relaxed),
+                    }
+                }
+                (Body::N256(grown), size)
+            }
+            Body::N256(_) => unreachable!("Node256 does not grow"),
+        };
+        let grown = VersionedNode::with_body(body);
+        grown.terminal.store(self.is_terminal(), Relaxed);
+        grown.size.store(size as u16, Relaxed);
+        grown
+    }
+
+    //children ordered by key
+    pub fn entries(&self) -> Vec<(u8, *mut VersionedNode)> {
+        let size = self.size.load(Acquire) as usize;
+        let mut entries: Vec<_> = match &self.body {
+            Body::N0 => Vec::new(),
+            Body::N4(n) => (0..size).map(|i| (n.keys[i].load(Relaxed), &n.children[i])).collect(),
+            Body::N16(n) => (0..size).map(|i| (n.keys[i].load(Relaxed), &n.children[i])).collect(),
+            Body::N48(n) => (0..=255u8)
+                .filter_map(|key| match n.index[key as usize].load(Acquire) {
+                    0 => None,
+                    slot => n.children.get(slot as usize - 1).map(|child| (key, child)),
+                })
+                .collect(),
+            Body::N256(n) => (0..=255u8).zip(n.children.iter()).collect(),
+        };
+        entries.sort_unstable_by_key(|(key, _)| *key);
+        entries
+            .into_iter()
+            .map(|(key, child)| (key, child.load(Acquire)))
+            .filter(|(_, child)| !child.is_null())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind(node: &VersionedNode) -> usize {
+        match node.body {
+            Body::N0 => 0,
+            Body::N4(_) => 4,
+            Body::N16(_) => 16,
+            Body::N48(_) => 48,
+            Body::N256(_) => 256,
+        }
+    }
+
+    #[test]
+    fn grow_keeps_children_through_every_node_type() {
+        let mut node = VersionedNode::leaf();
+        let mut leaves = Vec::new();
+        for key in 0..=255u8 {
+            if node.is_full() {
+                node = node.grow();
+            }
+            let leaf = VersionedNode::chain(&[]);
+            node.insert_child(key, leaf);
+            leaves.push(leaf);
+        }
+        assert_eq!(kind(&node), 256);
+        for key in 0..=255u8 {
+            assert_eq!(node.find_child(key), leaves[key as usize]);
+        }
+
+        //the smaller copies share children and are dropped without them
+        for (_, leaf) in node.entries() {
+            drop(unsafe { Box::from_raw(leaf) });
+        }
+    }
+
+    #[test]
+    fn versions_reject_reads_across_a_write() {
+        let node = VersionedNode::leaf();
+        let version = node.read_lock().unwrap();
+        assert!(node.check(version).is_ok());
+
+        node.upgrade_lock(version).unwrap();
+        //a second writer holding the old version can't get in
+        assert!(node.upgrade_lock(version).is_err());
+        node.unlock();
+        assert!(node.check(version).is_err());
+
+        let version = node.read_lock().unwrap();
+        node.upgrade_lock(version).unwrap();
+        node.unlock_obsolete();
+        assert!(node.read_lock().is_err());
+    }
+}
@@ -0,0 +1,46 @@
+use crate::trie::nodes::chars::{char_child, char_children};
+use crate::trie::nodes::node::NodeRef;
+use crate::trie::nodes::store::Store;
+
+//values matching pattern character for character in byte order, None matches any one character
+//literal characters are looked up directly, only wildcards fan out over the children
+pub fn wildcard<S: Store>(store: &S, root: NodeRef<S::Link>, pattern: &[Option<char>]) -> Vec<Vec<u8>> {
+    let mut found = Vec::new();
+    walk(store, root, pattern, &mut Vec::new(), &mut found);
+    found
+}
+
+fn walk<S: Store>(
+    store: &S,
+    node: NodeRef<S::Link>,
+    pattern: &[Option<char>],
+    value: &mut Vec<u8>,
+    found: &mut Vec<Vec<u8>>,
+) {
+    let (first, rest) = match pattern.split_first() {
+        Some(split) => split,
+        None => {
+            if node.is_terminal() {
+                found.push(value.clone());
+            }
+            return;
+        }
+    };
+    let length = value.len();
+    match first {
+        Some(c) => {
+            if let Some(child) = char_child(store, node, *c) {
+                value.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                walk(store, child, rest, value, found);
+            }
+        }
+        None => {
+            for (_, bytes, child) in char_children(store, node) {
+                value.extend_from_slice(&bytes);
+                walk(store, child, rest, value, found);
+                value.truncate(length);
+            }
+        }
+    }
+    value.truncate(length);
+}
@@ -0,0 +1,154 @@
+use crate::trie::enums::Case;
+use crate::trie::nodes::iter::Values;
+use crate::trie::nodes::node::NodeOption;
+use crate::trie::nodes::store::{Heap, Store};
+use crate::trie::tree::lossy;
+
+//immutable tree, every change returns a new tree
+//only the nodes on the changed path are copied, everything else is shared with the old tree
+#[derive(Clone)]
+pub struct PersistentTree {
+    case: Case,
+    root: NodeOption,
+}
+
+impl PersistentTree {
+    pub fn new(case: Case) -> Self {
+        PersistentTree::from_root(case, None)
+    }
+
+    pub(crate) fn from_root(case: Case, root: NodeOption) -> Self {
+        PersistentTree { case, root }
+    }
+
+    pub fn insert(&self, value: &str) -> Self {
+        let mut tree = self.clone();
+        if !value.is_empty() {
+            let case_corrected = self.case.corrected(value);
+
+            let root = tree.root.get_or_insert_with(|| Heap.leaf());
+            Heap.add(root, case_corrected.as_bytes());
+        }
+        tree
+    }
+
+    pub fn remove(&self, value: &str) -> Self {
+        let mut tree = self.clone();
+        let case_corrected = self.case.corrected(value);
+
+        if let Some(root) = tree.root.as_mut() {
+            Heap.remove(root, case_corrected.as_bytes());
+        }
+        tree
+    }
+
+    pub fn exists(&self, value: &str) -> bool {
+        let case_corrected = self.case.corrected(value);
+
+        if let Some(node) = self.root.as_ref() {
+            Heap.exists(node, case_corrected.as_bytes())
+        } else {
+            false
+        }
+    }
+
+    pub fn auto_complete(&self, value: &str, results: usize) -> Vec<String> {
+        let case_corrected = self.case.corrected(value);
+
+        let prefix = case_corrected.as_bytes();
+        let node = self.root.as_ref().and_then(|root| Heap.find(root, prefix));
+        Values::new(&Heap, node, prefix).take(results).map(lossy).collect()
+    }
+
+    //every value in order
+    pub fn iter(&self) -> impl Iterator<Item = String> + '_ {
+        let node = self.root.as_ref().map(|root| Heap.get(root));
+        Values::new(&Heap, node, &[]).map(lossy)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Case, PersistentTree};
+    use crate::trie::enums::Match;
+    use crate::trie::tree::Tree;
+    use crate::trie::nodes::node::NodeType;
+    use crate::trie::nodes::store::{Heap, Store};
+    use std::sync::Arc;
+
+    fn child(node: &NodeType, key: u8) -> &NodeType {
+        let (_, child) = Heap.get(node).children().into_iter().find(|(k, _)| *k == key).unwrap();
+        child
+    }
+
+    fn same_node(a: &NodeType, b: &NodeType) -> bool {
+        match (a, b) {
+            (NodeType::N4(a), NodeType::N4(b)) => Arc::ptr_eq(a, b),
+            (NodeType::N16(a), NodeType::N16(b)) => Arc::ptr_eq(a, b),
+            (NodeType::N48(a), NodeType::N48(b)) => Arc::ptr_eq(a, b),
+            (NodeType::N256(a), NodeType::N256(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn changes_leave_older_versions_intact() {
+        let empty = PersistentTree::new(Case::Sensitive);
+        let first = empty.insert("abc").insert("abd");
+        let second = first.insert("xyz");
+        let third = second.remove("abc");
+
+        assert!(!empty.exists("abc"));
+        assert!(first.exists("abc") && first.exists("abd") && !first.exists("xyz"));
+        assert!(second.exists("abc") && second.exists("xyz"));
+        assert!(!third.exists("abc") && third.exists("abd") && third.exists("xyz"));
+        assert_eq!(second.iter().collect::<Vec<_>>(), vec!["abc", "abd", "xyz"]);
+        assert_eq!(third.iter().collect::<Vec<_>>(), vec!["abd", "xyz"]);
+        assert_eq!(second.auto_complete("ab", 5), vec!["abc", "abd"]);
+        assert_eq!(third.auto_complete("ab", 5), vec!["abd"]);
+        assert!(empty.iter().next().is_none() && empty.auto_complete("", 5).is_empty());
+    }
+
+    #[test]
+    fn unchanged_subtrees_are_shared() {
+        let first = PersistentTree::new(Case::Sensitive)
+            .insert("abc")
+            .insert("abd")
+            .insert("xyz");
+        let second = first.insert("xyw");
+        let (first_root, second_root) = (first.root.as_ref().unwrap(), second.root.as_ref().unwrap());
+
+        //the path to the new value is copied, the branch beside it is not
+        assert!(!same_node(first_root, second_root));
+        assert!(!same_node(child(first_root, b'x'), child(second_root, b'x')));
+        assert!(same_node(child(first_root, b'a'), child(second_root, b'a')));
+
+        //removing a value that isn't there copies nothing
+        let third = second.remove("abz");
+        assert!(same_node(second_root, third.root.as_ref().unwrap()));
+
+        //nor does it in the tree a snapshot was taken from
+        let mut tree = Tree::new(Match::Exact, Case::Sensitive, false);
+        tree.add("abc");
+        tree.add("abd");
+        let before = tree.snapshot().unwrap();
+        assert!(!tree.remove("abz") && !tree.remove("xyz"));
+        let after = tree.snapshot().unwrap();
+        assert!(same_node(before.root.as_ref().unwrap(), after.root.as_ref().unwrap()));
+    }
+
+    #[test]
+    fn remove_drops_nodes_that_only_led_to_the_value() {
+        let tree = PersistentTree::new(Case::Sensitive)
+            .insert("ab")
+            .insert("abcd");
+        let removed = tree.remove("abcd");
+        let b = child(child(removed.root.as_ref().unwrap(), b'a'), b'b');
+        assert!(Heap.get(b).is_empty());
+        assert!(Heap.get(b).is_terminal());
+
+        let removed = removed.remove("ab");
+        assert!(Heap.get(removed.root.as_ref().unwrap()).is_empty());
+        assert!(tree.exists("ab") && tree.exists("abcd"));
+    }
+}
@@ -0,0 +1,380 @@
+use crate::trie::enums::{Case, Match};
+use crate::trie::errors::PrefixError;
+use crate::trie::tree::Tree;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+//address block such as 10.0.0.0/8, bits past the length are always zero
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Prefix {
+    address: IpAddr,
+    length: u8,
+}
+
+impl Prefix {
+    //host bits of address are cleared
+    pub fn new(address: IpAddr, length: u8) -> Result<Self, PrefixError> {
+        let bits = match address {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        if length > bits {
+            return Err(PrefixError {
+                prefix: format!("{}/{}", address, length),
+                reason: "length is longer than the address",
+            });
+        }
+        //shifting by the full width overflows, a full length prefix keeps every bit
+        let address = match address {
+            IpAddr::V4(v4) => {
+                let host = u32::MAX.checked_shr(length as u32).unwrap_or(0);
+                IpAddr::V4(Ipv4Addr::from(u32::from(v4) & !host))
+            }
+            IpAddr::V6(v6) => {
+                let host = u128::MAX.checked_shr(length as u32).unwrap_or(0);
+                IpAddr::V6(Ipv6Addr::from(u128::from(v6) & !host))
+            }
+        };
+        Ok(Prefix { address, length })
+    }
+
+    pub fn address(&self) -> IpAddr {
+        self.address
+    }
+
+    pub fn length(&self) -> u8 {
+        self.length
+    }
+
+    //the family byte, 4 or 6, then 0 and the byte for each whole byte of the prefix, then a closing
+    //byte holding the bits past the last whole byte under a leading 1, 1 alone when there are none
+    //whole address bytes branch through the adaptive nodes, and as the closing byte is never 0 it
+    //stays apart from the next whole byte and no route's key is a prefix of another's
+    fn key(&self) -> Vec<u8> {
+        let (family, bytes) = octets(self.address);
+        let (whole, bits) = (self.length as usize / 8, self.length as u32 % 8);
+        let mut key = Vec::with_capacity(2 + whole * 2);
+        key.push(family);
+        for byte in &bytes[..whole] {
+            key.extend_from_slice(&[0, *byte]);
+        }
+        key.push(closing(bytes.get(whole).copied().unwrap_or(0), bits));
+        key
+    }
+
+    //the prefix a key starts with and the rest of the key after it
+    fn from_key(key: &[u8]) -> (Prefix, &[u8]) {
+        let mut bytes = Vec::new();
+        let mut rest = &key[1..];
+        while let [0, byte, more @ ..] = rest {
+            bytes.push(*byte);
+            rest = more;
+        }
+        let (closing, rest) = rest.split_first().unwrap();
+        let bits = 7 - closing.leading_zeros();
+        let length = bytes.len() * 8 + bits as usize;
+        if bits > 0 {
+            bytes.push((closing & !(1 << bits)) << (8 - bits));
+        }
+        let address = match key[0] {
+            4 => {
+                bytes.resize(4, 0);
+                IpAddr::V4(Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3]))
+            }
+            _ => {
+                let mut octets = [0; 16];
+                octets[..bytes.len()].copy_from_slice(&bytes);
+                IpAddr::V6(Ipv6Addr::from(octets))
+            }
+        };
+        (Prefix { address, length: length as u8 }, rest)
+    }
+
+    //whether the prefix is this one or a more specific one inside it
+    fn within(&self, other: &Prefix) -> bool {
+        self.length >= other.length && Prefix::new(self.address, other.length).ok().as_ref() == Some(other)
+    }
+}
+
+fn octets(address: IpAddr) -> (u8, Vec<u8>) {
+    match address {
+        IpAddr::V4(v4) => (4, v4.octets().to_vec()),
+        IpAddr::V6(v6) => (6, v6.octets().to_vec()),
+    }
+}
+
+//1 above the top bits of byte, so every length of leftover bits gets its own range of values
+fn closing(byte: u8, bits: u32) -> u8 {
+    1 << bits | byte.checked_shr(8 - bits).unwrap_or(0)
+}
+
+//slot numbers are kept short, as few big-endian bytes as they need
+fn slot_key(slot: usize) -> Vec<u8> {
+    let bytes = slot.to_be_bytes();
+    let skip = bytes.iter().take_while(|&&byte| byte == 0).count().min(bytes.len() - 1);
+    bytes[skip..].to_vec()
+}
+
+fn slot(key: &[u8]) -> usize {
+    key.iter().fold(0, |slot, &byte| slot << 8 | byte as usize)
+}
+
+impl fmt::Display for Prefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.address, self.length)
+    }
+}
+
+impl FromStr for Prefix {
+    type Err = PrefixError;
+
+    fn from_str(value: &str) -> Result<Self, PrefixError> {
+        let error = |reason| PrefixError { prefix: String::from(value), reason };
+        let (address, length) = value.split_once('/').ok_or_else(|| error("missing /length"))?;
+        let address = address.parse().map_err(|_| error("not an ip address"))?;
+        let length = length.parse().map_err(|_| error("length is not a number"))?;
+        Prefix::new(address, length)
+    }
+}
+
+//longest prefix match over ipv4 and ipv6 routes, each route maps a prefix to a next hop
+pub struct RoutingTable<V> {
+    //every route's key, see Prefix::key, followed by the slot its hop is kept in
+    tree: Tree,
+    hops: Vec<Option<V>>,
+    //slots of withdrawn routes, taken again before hops grows
+    free: Vec<usize>,
+}
+
+impl<V> RoutingTable<V> {
+    pub fn new() -> Self {
+        RoutingTable {
+            tree: Tree::new(Match::Exact, Case::Sensitive, false),
+            hops: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.hops.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    //the only key below a route's key is its slot
+    fn slot(&self, key: &[u8]) -> Option<usize> {
+        self.tree.prefix_bytes(key).next().map(|stored| slot(&stored[key.len()..]))
+    }
+
+    fn route(&self, stored: &[u8]) -> (Prefix, &V) {
+        let (prefix, rest) = Prefix::from_key(stored);
+        (prefix, self.hops[slot(rest)].as_ref().unwrap())
+    }
+
+    //returns the hop the prefix was routed to before
+    pub fn insert(&mut self, prefix: Prefix, hop: V) -> Option<V> {
+        let mut key = prefix.key();
+        if let Some(slot) = self.slot(&key) {
+            return self.hops[slot].replace(hop);
+        }
+        let slot = match self.free.pop() {
+            Some(slot) => slot,
+            None => {
+                self.hops.push(None);
+                self.hops.len() - 1
+            }
+        };
+        self.hops[slot] = Some(hop);
+        key.extend(slot_key(slot));
+        self.tree.insert_bytes(&key);
+        None
+    }
+
+    //withdraw the route, returning its hop
+    pub fn remove(&mut self, prefix: &Prefix) -> Option<V> {
+        let mut key = prefix.key();
+        let slot = self.slot(&key)?;
+        key.extend(slot_key(slot));
+        self.tree.remove_bytes(&key);
+        self.free.push(slot);
+        self.hops[slot].take()
+    }
+
+    //hop for exactly this prefix
+    pub fn get(&self, prefix: &Prefix) -> Option<&V> {
+        self.hops[self.slot(&prefix.key())?].as_ref()
+    }
+
+    //most specific route covering the address
+    pub fn lookup(&self, address: IpAddr) -> Option<(Prefix, &V)> {
+        self.matches(address).pop()
+    }
+
+    //every route covering the address, least specific first
+    //one walk down the address's whole bytes, looking beside each for the routes ending there
+    pub fn matches(&self, address: IpAddr) -> Vec<(Prefix, &V)> {
+        let (family, bytes) = octets(address);
+        let mut input = vec![family];
+        for byte in &bytes {
+            input.extend_from_slice(&[0, *byte]);
+        }
+        //routes end after the family byte and after every whole byte, at odd depths
+        let closings = |depth: usize| match bytes.get(depth / 2) {
+            _ if depth.is_multiple_of(2) => Vec::new(),
+            Some(&byte) => (0..8).map(|bits| closing(byte, bits)).collect(),
+            None => vec![closing(0, 0)],
+        };
+        self.tree
+            .branches_along(&input, closings)
+            .iter()
+            .map(|stored| self.route(stored))
+            .collect()
+    }
+
+    //the prefix's own route and every more specific one inside it, in address order
+    pub fn covered(&self, prefix: &Prefix) -> impl Iterator<Item = (Prefix, &V)> + '_ {
+        //every route inside starts with the prefix's whole bytes, the rest are sorted out by address
+        let mut key = prefix.key();
+        key.pop();
+        let mut covered = self
+            .tree
+            .prefix_bytes(&key)
+            .map(|stored| self.route(&stored))
+            .filter(|(route, _)| route.within(prefix))
+            .collect::<Vec<_>>();
+        covered.sort_unstable_by_key(|(route, _)| (route.address, route.length));
+        covered.into_iter()
+    }
+
+    //every route, ipv4 before ipv6
+    pub fn iter(&self) -> impl Iterator<Item = (Prefix, &V)> + '_ {
+        let mut routes = self.tree.iter_bytes().map(|stored| self.route(&stored)).collect::<Vec<_>>();
+        routes.sort_unstable_by_key(|(route, _)| (route.address, route.length));
+        routes.into_iter()
+    }
+}
+
+impl<V> Default for RoutingTable<V> {
+    fn default() -> Self {
+        RoutingTable::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Prefix, RoutingTable};
+    use std::net::{IpAddr, Ipv4Addr};
+
+    fn prefix(value: &str) -> Prefix {
+        value.parse().unwrap()
+    }
+
+    fn address(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_and_masks_prefixes() {
+        assert_eq!(prefix("10.1.2.3/8").to_string(), "10.0.0.0/8");
+        assert_eq!(prefix("10.255.0.0/12").to_string(), "10.240.0.0/12");
+        assert_eq!(prefix("1.2.3.4/0").to_string(), "0.0.0.0/0");
+        assert_eq!(prefix("2001:db8::1/32").to_string(), "2001:db8::/32");
+        assert_eq!(prefix("::1/128").to_string(), "::1/128");
+        for invalid in &["10.0.0.0", "10.0.0.0/33", "::/129", "10.0.0/8", "10.0.0.0/x"] {
+            assert!(invalid.parse::<Prefix>().is_err(), "{}", invalid);
+        }
+
+        //full length routes come back out of the tree unchanged
+        let mut table = RoutingTable::new();
+        for route in &["10.2.3.4/32", "::1/128", "ffff::/1"] {
+            table.insert(prefix(route), ());
+            assert_eq!(table.iter().find(|(found, _)| *found == prefix(route)).map(|(found, _)| found), Some(prefix(route)));
+        }
+        assert_eq!(table.lookup(address("10.2.3.4")).map(|(found, _)| found), Some(prefix("10.2.3.4/32")));
+        assert_eq!(table.lookup(address("10.2.3.5")), None);
+    }
+
+    fn hop<'a>(table: &RoutingTable<&'a str>, value: &str) -> Option<(String, &'a str)> {
+        table.lookup(address(value)).map(|(prefix, hop)| (prefix.to_string(), *hop))
+    }
+
+    #[test]
+    fn most_specific_route_wins() {
+        let mut table = RoutingTable::new();
+        table.insert(prefix("0.0.0.0/0"), "default");
+        table.insert(prefix("10.0.0.0/8"), "a");
+        table.insert(prefix("10.240.0.0/12"), "b");
+        table.insert(prefix("10.240.1.0/24"), "c");
+        table.insert(prefix("::/0"), "v6 default");
+        table.insert(prefix("2001:db8::/32"), "v6");
+
+        assert_eq!(hop(&table, "10.240.1.9"), Some((String::from("10.240.1.0/24"), "c")));
+        assert_eq!(hop(&table, "10.241.0.1"), Some((String::from("10.240.0.0/12"), "b")));
+        assert_eq!(hop(&table, "10.128.0.1"), Some((String::from("10.0.0.0/8"), "a")));
+        assert_eq!(hop(&table, "192.168.0.1"), Some((String::from("0.0.0.0/0"), "default")));
+        assert_eq!(hop(&table, "2001:db8:1::1"), Some((String::from("2001:db8::/32"), "v6")));
+        assert_eq!(hop(&table, "2001:db9::1"), Some((String::from("::/0"), "v6 default")));
+
+        let matches = table.matches(address("10.240.1.1")).into_iter().map(|(_, hop)| *hop).collect::<Vec<_>>();
+        assert_eq!(matches, vec!["default", "a", "b", "c"]);
+
+        //withdrawing falls back to the next most specific route
+        assert_eq!(table.remove(&prefix("10.240.0.0/12")), Some("b"));
+        assert_eq!(table.remove(&prefix("10.240.0.0/12")), None);
+        assert_eq!(hop(&table, "10.241.0.1"), Some((String::from("10.0.0.0/8"), "a")));
+        assert_eq!(table.insert(prefix("10.0.0.0/8"), "a2"), Some("a"));
+        assert_eq!(table.get(&prefix("10.0.0.0/8")), Some(&"a2"));
+        assert_eq!(table.len(), 5);
+    }
+
+    #[test]
+    fn routes_branch_on_whole_address_bytes() {
+        let mut table = RoutingTable::new();
+        table.insert(prefix("10.1.2.0/24"), 0);
+        //family, three whole bytes behind their markers, the closing byte and one slot byte
+        assert_eq!(table.tree.stats().max_depth, 9);
+        table.insert(prefix("2001:db8::1/128"), 1);
+        assert_eq!(table.tree.stats().max_depth, 1 + 32 + 1 + 1);
+
+        //every length from /0 to /32 under one address, each found exactly where it ends
+        let mut table = RoutingTable::new();
+        for length in 0..=32 {
+            table.insert(Prefix::new(address("172.31.255.255"), length).unwrap(), length);
+        }
+        for length in 0..32 {
+            //flipping the bit after a prefix leaves it the most specific route still covering
+            let flipped = Ipv4Addr::from(0xac1f_ffffu32 ^ (0x8000_0000 >> length));
+            assert_eq!(table.lookup(IpAddr::V4(flipped)).map(|(_, hop)| *hop), Some(length), "{}", flipped);
+        }
+        assert_eq!(table.lookup(address("172.31.255.255")).map(|(_, hop)| *hop), Some(32));
+        assert_eq!(table.matches(address("172.31.255.255")).len(), 33);
+        assert_eq!(table.covered(&prefix("172.31.0.0/16")).count(), 17);
+
+        //withdrawn routes give their slots to the next ones inserted
+        for length in 0..=32 {
+            table.remove(&Prefix::new(address("172.31.255.255"), length).unwrap());
+        }
+        assert!(table.is_empty() && table.tree.iter_bytes().next().is_none());
+        table.insert(prefix("10.0.0.0/8"), 7);
+        assert_eq!((table.hops.len(), table.len()), (33, 1));
+    }
+
+    #[test]
+    fn iterates_covered_routes_in_address_order() {
+        let mut table = RoutingTable::new();
+        for (i, route) in ["10.0.0.0/8", "10.128.0.0/9", "10.0.0.0/16", "11.0.0.0/8", "10.1.0.0/16", "::/0"]
+            .iter()
+            .enumerate()
+        {
+            table.insert(prefix(route), i);
+        }
+
+        let covered = table.covered(&prefix("10.0.0.0/8")).map(|(prefix, _)| prefix.to_string()).collect::<Vec<_>>();
+        assert_eq!(covered, vec!["10.0.0.0/8", "10.0.0.0/16", "10.1.0.0/16", "10.128.0.0/9"]);
+        assert_eq!(table.covered(&prefix("10.0.0.0/7")).count(), 5);
+        assert_eq!(table.iter().last().map(|(prefix, hop)| (prefix.to_string(), *hop)), Some((String::from("::/0"), 5)));
+    }
+}
@@ -0,0 +1,175 @@
+use crate::trie::enums::{Case, Match};
+use crate::trie::tree::Tree;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::sync::RwLock;
+
+//tree shared between threads by splitting the root into independently locked subtrees
+//values go to the shard picked by their first byte modulo the shard count, so consecutive
+//letters land on different shards and writers to neighbouring prefixes don't contend
+pub struct ShardedTree {
+    case: Case,
+    shards: Vec<RwLock<Tree>>,
+}
+
+impl ShardedTree {
+    pub fn new(matching: Match, case: Case, compress: bool, shards: usize) -> Self {
+        assert!(shards > 0, "a sharded tree needs at least one shard");
+        ShardedTree {
+            case,
+            shards: (0..shards)
+                .map(|_| RwLock::new(Tree::new(matching, case, compress)))
+                .collect(),
+        }
+    }
+
+    //values are case corrected once here, the shards get the corrected bytes and leave them as they are
+    //shard holding values that start with the given case corrected value
+    fn shard(&self, case_corrected: &str) -> Option<&RwLock<Tree>> {
+        let first = *case_corrected.as_bytes().first()?;
+        Some(&self.shards[first as usize % self.shards.len()])
+    }
+
+    pub fn add(&self, value: &str) {
+        let case_corrected = self.case.corrected(value);
+        if let Some(shard) = self.shard(&case_corrected) {
+            shard.write().unwrap().insert_bytes(case_corrected.as_bytes());
+        }
+    }
+
+    pub fn exists(&self, value: &str) -> bool {
+        let case_corrected = self.case.corrected(value);
+        self.shard(&case_corrected)
+            .is_some_and(|shard| shard.read().unwrap().contains_bytes(case_corrected.as_bytes()))
+    }
+
+    //up to results values starting with value, in order
+    //an empty value matches every shard so their completions are merged
+    pub fn auto_complete(&self, value: &str, results: usize) -> Vec<String> {
+        let case_corrected = self.case.corrected(value);
+        match self.shard(&case_corrected) {
+            Some(shard) => shard
+                .read()
+                .unwrap()
+                .prefix_bytes(case_corrected.as_bytes())
+                .take(results)
+                .map(|value| String::from_utf8(value).unwrap())
+                .collect(),
+            None => Merge::new(
+                self.shards
+                    .iter()
+                    .map(|shard| shard.read().unwrap().iter().take(results).collect::<Vec<_>>().into_iter()),
+            )
+            .take(results)
+            .collect(),
+        }
+    }
+
+    //every value in order
+    //each shard is read under its own lock in turn, so values added to a shard that was
+    //already read are missed
+    pub fn iter(&self) -> impl Iterator<Item = String> {
+        Merge::new(
+            self.shards
+                .iter()
+                .map(|shard| shard.read().unwrap().iter().collect::<Vec<_>>().into_iter()),
+        )
+    }
+}
+
+//k-way merge of sorted iterators, the smallest head comes out first
+struct Merge<I: Iterator> {
+    iters: Vec<I>,
+    //next value of each iterator tagged with its index in iters
+    heads: BinaryHeap<Reverse<(I::Item, usize)>>,
+}
+
+impl<I> Merge<I>
+where
+    I: Iterator,
+    I::Item: Ord,
+{
+    fn new(iters: impl IntoIterator<Item = I>) -> Self {
+        let mut iters = iters.into_iter().collect::<Vec<_>>();
+        let heads = iters
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(i, iter)| Some(Reverse((iter.next()?, i))))
+            .collect();
+        Merge { iters, heads }
+    }
+}
+
+impl<I> Iterator for Merge<I>
+where
+    I: Iterator,
+    I::Item: Ord,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let Reverse((value, i)) = self.heads.pop()?;
+        if let Some(next) = self.iters[i].next() {
+            self.heads.push(Reverse((next, i)));
+        }
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Case, Match, Merge, ShardedTree};
+    use std::thread;
+
+    #[test]
+    fn merge_interleaves_sorted_inputs() {
+        let merged = Merge::new(vec![vec![1, 4, 7].into_iter(), vec![].into_iter(), vec![2, 3, 9].into_iter()]);
+        assert_eq!(merged.collect::<Vec<_>>(), vec![1, 2, 3, 4, 7, 9]);
+    }
+
+    #[test]
+    fn behaves_like_a_single_tree() {
+        let tree = ShardedTree::new(Match::Exact, Case::Insensitve, false, 3);
+        let words = vec!["b", "Abc", "ab", "c", "abd", "ba", "d", "dd", "e"];
+        for word in &words {
+            tree.add(word);
+        }
+        tree.add("");
+
+        for word in &words {
+            assert!(tree.exists(word));
+        }
+        assert!(!tree.exists(""));
+        assert!(!tree.exists("f"));
+
+        let mut lowercase = words.iter().map(|word| word.to_lowercase()).collect::<Vec<_>>();
+        lowercase.sort();
+        assert_eq!(tree.iter().collect::<Vec<_>>(), lowercase);
+        assert_eq!(tree.auto_complete("", 4), &lowercase[..4]);
+        assert_eq!(tree.auto_complete("AB", 5), vec!["ab", "abc", "abd"]);
+    }
+
+    #[test]
+    fn concurrent_adds_land_in_order() {
+        let tree = ShardedTree::new(Match::Exact, Case::Sensitive, false, 4);
+        let word = |writer: usize, i: usize| format!("{}{}", (b'a' + (i % 26) as u8) as char, i * 4 + writer);
+
+        thread::scope(|scope| {
+            for writer in 0..4 {
+                let tree = &tree;
+                scope.spawn(move || {
+                    for i in 0..1000 {
+                        tree.add(&word(writer, i));
+                        assert!(tree.exists(&word(writer, i)));
+                    }
+                });
+            }
+        });
+
+        let mut expected = (0..4)
+            .flat_map(|writer| (0..1000).map(move |i| word(writer, i)))
+            .collect::<Vec<_>>();
+        expected.sort();
+        assert_eq!(tree.iter().collect::<Vec<_>>(), expected);
+    }
+}
@@ -0,0 +1,169 @@
+use crate::trie::enums::NodeKind;
+use crate::trie::nodes::node::NodeRef;
+use crate::trie::nodes::store::Store;
+use crate::trie::nodes::{
+    node0::Node0, node16::Node16, node256::Node256, node4::Node4, node48::Node48,
+};
+use std::fmt;
+use std::mem::size_of;
+
+//shape of a tree, see Tree::stats
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Stats {
+    pub keys: usize,
+    //bytes in all keys together
+    pub key_bytes: usize,
+    pub node0: NodeStats,
+    pub node4: NodeStats,
+    pub node16: NodeStats,
+    pub node48: NodeStats,
+    pub node256: NodeStats,
+    //bytes in the longest key
+    pub max_depth: usize,
+    //number of nodes at each depth, the root is at depth 0
+    pub depths: Vec<usize>,
+    //bytes taken by the node structs themselves, allocator overhead isn't counted
+    pub bytes: usize,
+}
+
+//totals for the nodes of one type
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NodeStats {
+    pub count: usize,
+    //child slots in use and available across all the nodes
+    pub children: usize,
+    pub slots: usize,
+    pub bytes: usize,
+}
+
+impl NodeStats {
+    //share of the slots in use, leaves have no slots and count as full
+    pub fn fill(&self) -> f64 {
+        if self.slots == 0 {
+            1.0
+        } else {
+            self.children as f64 / self.slots as f64
+        }
+    }
+
+    pub fn wasted_slots(&self) -> usize {
+        self.slots - self.children
+    }
+
+    fn count(&mut self, children: usize, slots: usize, bytes: usize) {
+        self.count += 1;
+        self.children += children;
+        self.slots += slots;
+        self.bytes += bytes;
+    }
+}
+
+//one node on the way down to a value, see Tree::path
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathNode {
+    //byte leading to the node from its parent, none for the root
+    pub key: Option<u8>,
+    pub kind: NodeKind,
+    //number of children
+    pub size: usize,
+    pub terminal: bool,
+}
+
+impl PathNode {
+    pub(crate) fn of<L>(key: Option<u8>, node: NodeRef<L>) -> PathNode {
+        PathNode {
+            key,
+            kind: node.kind(),
+            size: node.children().len(),
+            terminal: node.is_terminal(),
+        }
+    }
+}
+
+impl Stats {
+    pub fn nodes(&self) -> usize {
+        self.by_kind().iter().map(|(_, stats)| stats.count).sum()
+    }
+
+    //per type totals in order of node size
+    pub fn by_kind(&self) -> [(NodeKind, &NodeStats); 5] {
+        [
+            (NodeKind::Node0, &self.node0),
+            (NodeKind::Node4, &self.node4),
+            (NodeKind::Node16, &self.node16),
+            (NodeKind::Node48, &self.node48),
+            (NodeKind::Node256, &self.node256),
+        ]
+    }
+
+    pub fn wasted_slots(&self) -> usize {
+        self.by_kind().iter().map(|(_, stats)| stats.wasted_slots()).sum()
+    }
+
+    pub fn average_key_length(&self) -> f64 {
+        if self.keys == 0 {
+            0.0
+        } else {
+            self.key_bytes as f64 / self.keys as f64
+        }
+    }
+
+    pub(crate) fn collect<S: Store>(store: &S, root: NodeRef<S::Link>) -> Stats {
+        let mut stats = Stats::default();
+        let mut pending = vec![(root, 0)];
+        while let Some((node, depth)) = pending.pop() {
+            if node.is_terminal() {
+                stats.keys += 1;
+                stats.key_bytes += depth;
+            }
+            stats.max_depth = stats.max_depth.max(depth);
+            if stats.depths.len() == depth {
+                stats.depths.push(0);
+            }
+            stats.depths[depth] += 1;
+
+            let children = node.children();
+            match node {
+                NodeRef::N0(_) => stats.node0.count(0, 0, size_of::<Node0>()),
+                NodeRef::N4(_) => stats.node4.count(children.len(), 4, size_of::<Node4<S::Link>>()),
+                NodeRef::N16(_) => stats.node16.count(children.len(), 16, size_of::<Node16<S::Link>>()),
+                NodeRef::N48(_) => stats.node48.count(children.len(), 48, size_of::<Node48<S::Link>>()),
+                NodeRef::N256(_) => stats.node256.count(children.len(), 256, size_of::<Node256<S::Link>>()),
+            }
+            for (_, child) in children {
+                pending.push((store.get(child), depth + 1));
+            }
+        }
+        stats.bytes = stats.by_kind().iter().map(|(_, stats)| stats.bytes).sum();
+        stats
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "keys       {}", self.keys)?;
+        writeln!(f, "  average length  {:.2}", self.average_key_length())?;
+        writeln!(f, "nodes      {}", self.nodes())?;
+        for (kind, stats) in self.by_kind().iter() {
+            let fill = match stats.slots {
+                0 => String::from("-"),
+                _ => format!("{:.1}%", stats.fill() * 100.0),
+            };
+            writeln!(
+                f,
+                "  {:<9}{:<10}fill {:>6}  wasted slots {:<10}{} bytes",
+                format!("{:?}", kind),
+                stats.count,
+                fill,
+                stats.wasted_slots(),
+                stats.bytes
+            )?;
+        }
+        writeln!(f, "max depth  {}", self.max_depth)?;
+        for (depth, nodes) in self.depths.iter().enumerate() {
+            writeln!(f, "  {:<9}{} nodes", depth, nodes)?;
+        }
+        writeln!(f, "wasted slots  {}", self.wasted_slots())?;
+        write!(f, "memory     {} bytes", self.bytes)
+    }
+}
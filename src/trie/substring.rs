@@ -0,0 +1,122 @@
+use crate::trie::enums::{Case, Match};
+use crate::trie::tree::Tree;
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+
+//longest n-gram taken at each position, keys shorter than this or ending sooner give shorter ones
+const GRAM: usize = 3;
+
+//companion index a PrefixPostfix tree keeps next to its values for suffix and infix searches
+//reversed holds every key back to front, so a suffix of a key is a prefix of its reversed key
+//each key is copied once into a slot of keys, owners maps the bytes starting at every position
+//of every key, up to GRAM long, to the slots of the keys they were taken from
+//the empty key has no grams and is never given a slot
+pub(crate) struct Substrings {
+    reversed: Tree,
+    keys: Vec<Option<Vec<u8>>>,
+    free: Vec<u32>,
+    owners: BTreeMap<Vec<u8>, BTreeSet<u32>>,
+}
+
+//n-gram starting at each position of key
+fn grams(key: &[u8]) -> impl Iterator<Item = &[u8]> {
+    (0..key.len()).map(move |i| &key[i..key.len().min(i + GRAM)])
+}
+
+fn reverse(key: &[u8]) -> Vec<u8> {
+    key.iter().rev().copied().collect()
+}
+
+impl Substrings {
+    pub(crate) fn new() -> Self {
+        Substrings {
+            reversed: Tree::new(Match::Exact, Case::Sensitive, false),
+            keys: Vec::new(),
+            free: Vec::new(),
+            owners: BTreeMap::new(),
+        }
+    }
+
+    //keys already indexed are left alone
+    pub(crate) fn insert(&mut self, key: &[u8]) {
+        let reversed = reverse(key);
+        if self.reversed.contains_bytes(&reversed) {
+            return;
+        }
+        self.reversed.insert_bytes(&reversed);
+        if key.is_empty() {
+            return;
+        }
+
+        let id = match self.free.pop() {
+            Some(id) => {
+                self.keys[id as usize] = Some(key.to_vec());
+                id
+            }
+            None => {
+                self.keys.push(Some(key.to_vec()));
+                u32::try_from(self.keys.len() - 1).expect("substring index holds at most u32::MAX keys")
+            }
+        };
+        for gram in grams(key) {
+            self.owners.entry(gram.to_vec()).or_default().insert(id);
+        }
+    }
+
+    //grams no other key has are dropped along with the key
+    pub(crate) fn remove(&mut self, key: &[u8]) {
+        self.reversed.remove_bytes(&reverse(key));
+        let id = match self.id(key) {
+            Some(id) => id,
+            None => return,
+        };
+        self.keys[id as usize] = None;
+        self.free.push(id);
+        for gram in grams(key) {
+            if let Some(ids) = self.owners.get_mut(gram) {
+                ids.remove(&id);
+                if ids.is_empty() {
+                    self.owners.remove(gram);
+                }
+            }
+        }
+    }
+
+    //slot of key, found among the keys sharing its rarest gram
+    fn id(&self, key: &[u8]) -> Option<u32> {
+        let rarest = grams(key).filter_map(|gram| self.owners.get(gram)).min_by_key(|ids| ids.len())?;
+        rarest.iter().copied().find(|&id| self.keys[id as usize].as_deref() == Some(key))
+    }
+
+    //keys ending with suffix, in byte order
+    pub(crate) fn ends_with(&self, suffix: &[u8]) -> Vec<Vec<u8>> {
+        let mut found = self.reversed.prefix_bytes(&reverse(suffix)).map(|key| reverse(&key)).collect::<Vec<_>>();
+        found.sort_unstable();
+        found
+    }
+
+    //keys holding infix anywhere, in byte order, the empty infix is left to the caller
+    //an infix up to GRAM long starts the gram taken where it occurs, so the keys under every
+    //gram starting with it are exactly the keys holding it
+    //a longer infix can only be in keys holding each of its grams, those under its rarest
+    //gram are checked one by one
+    pub(crate) fn contains(&self, infix: &[u8]) -> Vec<Vec<u8>> {
+        let mut found = BTreeSet::new();
+        if infix.len() <= GRAM {
+            let under = self.owners.range(infix.to_vec()..).take_while(|(gram, _)| gram.starts_with(infix));
+            for (_, ids) in under {
+                found.extend(ids.iter().map(|&id| self.key(id)));
+            }
+        } else {
+            let rarest = infix.windows(GRAM).map(|gram| self.owners.get(gram)).min_by_key(|ids| ids.map_or(0, |ids| ids.len()));
+            if let Some(ids) = rarest.flatten() {
+                found.extend(ids.iter().map(|&id| self.key(id)).filter(|key| key.windows(infix.len()).any(|window| window == infix)));
+            }
+        }
+        found.into_iter().map(<[u8]>::to_vec).collect()
+    }
+
+    fn key(&self, id: u32) -> &[u8] {
+        self.keys[id as usize].as_deref().expect("owners only hold slots of indexed keys")
+    }
+}
@@ -1,6 +1,25 @@
-use crate::trie::enums::{Case, Match};
-use crate::trie::nodes::node::{Node, NodeOption};
-use crate::trie::nodes::node0::Node0;
+use crate::trie::enums::{Allocation, Case, Match, Occurrences};
+use crate::trie::errors::{InvalidNode, SnapshotError, UnsortedError};
+use crate::trie::matcher::Matcher;
+use crate::trie::nodes::automaton::Automaton;
+use crate::trie::nodes::arena::{Arena, NodeId};
+use crate::trie::nodes::bulk::{build_parallel, SortedBuilder};
+use crate::trie::nodes::chars::char_children;
+use crate::trie::nodes::dot::dot;
+use crate::trie::nodes::fuzzy::fuzzy;
+use crate::trie::nodes::iter::Values;
+use crate::trie::nodes::segment::segments;
+use crate::trie::nodes::serialize::{self, invalid};
+use crate::trie::nodes::node::{NodeLocation, NodeOption};
+use crate::trie::nodes::store::{Heap, Store};
+use crate::trie::nodes::validate::validate;
+use crate::trie::nodes::wildcard::wildcard;
+use crate::trie::persistent::PersistentTree;
+use crate::trie::stats::{PathNode, Stats};
+use crate::trie::substring::Substrings;
+use std::ops::{Bound, Range, RangeBounds};
+use std::io::{self, Read, Write};
+use std::thread;
 
 //https://db.in.tum.de/~leis/papers/ART.pdf
 // [x] Trie
@@ -12,62 +31,631 @@ use crate::trie::nodes::node0::Node0;
 pub struct Tree {
     matching: Match,
     case: Case,
-    root: NodeOption,
+    storage: Storage,
+    //suffix and infix index, only PrefixPostfix trees keep one
+    substrings: Option<Box<Substrings>>,
+    #[allow(dead_code)] //compression is not implemented yet
     compress: bool,
 }
 
+//the root together with the store that owns the nodes below it
+//there is only one per tree so the size of the arena variant doesn't matter
+#[allow(clippy::large_enum_variant)]
+enum Storage {
+    Heap(Heap, NodeOption),
+    Arena(Arena, Option<NodeId>),
+}
+
+//run the same generic body against whichever store the tree was built with
+macro_rules! with_store {
+    ($storage:expr, $store:ident, $root:ident => $body:expr) => {
+        match $storage {
+            Storage::Heap($store, $root) => $body,
+            Storage::Arena($store, $root) => $body,
+        }
+    };
+}
+
+//start of every saved tree, the last byte is the format version
+const MAGIC: &[u8; 5] = b"ARST\x01";
+
 //TODO add support for compressions of single child nodes
 //TODO add support for arbitrary indexing implementation
 //TODO add fn options() for discovering autocomplete options
 impl Tree {
     pub fn new(matching: Match, case: Case, compress: bool) -> Self {
+        Tree::with_allocation(matching, case, compress, Allocation::Heap)
+    }
+
+    pub fn with_allocation(
+        matching: Match,
+        case: Case,
+        compress: bool,
+        allocation: Allocation,
+    ) -> Self {
+        let storage = match allocation {
+            Allocation::Heap => Storage::Heap(Heap, None),
+            Allocation::Arena => Storage::Arena(Arena::new(), None),
+        };
+        let substrings = match matching {
+            Match::PrefixPostfix => Some(Box::new(Substrings::new())),
+            _ => None,
+        };
         Tree {
             matching,
             case,
-            root: NodeOption::default(),
+            storage,
+            substrings,
             compress
         }
     }
 
+    //bulk builds and loads fill the store directly, the substring index is built after them
+    fn reindex(&mut self) {
+        if self.substrings.is_some() {
+            let keys = self.iter_bytes().collect::<Vec<_>>();
+            let mut substrings = Substrings::new();
+            for key in keys {
+                substrings.insert(&key);
+            }
+            self.substrings = Some(Box::new(substrings));
+        }
+    }
+
+    //builds the tree in one pass with every node created at its final size
+    //values must be sorted by their bytes after case correction
+    pub fn from_sorted_iter<I, T>(
+        matching: Match,
+        case: Case,
+        compress: bool,
+        allocation: Allocation,
+        values: I,
+    ) -> Result<Self, UnsortedError>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        let mut tree = Tree::with_allocation(matching, case, compress, allocation);
+        let case = &tree.case;
+        with_store!(&mut tree.storage, store, root => {
+            let mut builder = SortedBuilder::new();
+            for (position, value) in values.into_iter().enumerate() {
+                let value = value.as_ref();
+                if value.is_empty() {
+                    continue;
+                }
+                let case_corrected = case.corrected(value);
+                if builder.push(store, case_corrected.as_bytes()).is_err() {
+                    return Err(UnsortedError { position, value: String::from(value) });
+                }
+            }
+            *root = Some(builder.finish(store));
+        });
+        tree.reindex();
+        Ok(tree)
+    }
+
+    //builds the subtree under each leading byte on a separate thread
+    //values can come in any order, the result is the same tree sequential adds would give
+    pub fn par_build<I, T>(
+        matching: Match,
+        case: Case,
+        compress: bool,
+        allocation: Allocation,
+        values: I,
+    ) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        let workers = thread::available_parallelism().map_or(1, |n| n.get());
+        Tree::par_build_with(matching, case, compress, allocation, values, workers)
+    }
+
+    fn par_build_with<I, T>(
+        matching: Match,
+        case: Case,
+        compress: bool,
+        allocation: Allocation,
+        values: I,
+        workers: usize,
+    ) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        let mut tree = Tree::with_allocation(matching, case, compress, allocation);
+        let mut partitions = vec![Vec::new(); 256];
+        for value in values {
+            let value = value.as_ref();
+            if value.is_empty() {
+                continue;
+            }
+            let case_corrected = tree.case.corrected(value).into_owned().into_bytes();
+            partitions[case_corrected[0] as usize].push(case_corrected);
+        }
+        let partitions = partitions
+            .into_iter()
+            .enumerate()
+            .filter(|(_, values)| !values.is_empty())
+            .map(|(key, values)| (key as u8, values))
+            .collect::<Vec<_>>();
+
+        if !partitions.is_empty() {
+            with_store!(&mut tree.storage, store, root => {
+                *root = Some(build_parallel(store, partitions, workers));
+            });
+        }
+        tree.reindex();
+        tree
+    }
+
+    //the empty string is ignored, use insert_bytes for the empty key
     pub fn add(&mut self, value: &str) {
         if !value.is_empty() {
-            let case_corrected = match self.case {
-                Case::Insensitve => value.to_lowercase(),
-                Case::Sensitive => String::from(value),
-            };
+            let case_corrected = self.case.corrected(value);
+
+            self.insert_bytes(case_corrected.as_bytes());
+        }
+    }
+
+    //returns whether the value was in the tree
+    pub fn remove(&mut self, value: &str) -> bool {
+        let case_corrected = self.case.corrected(value);
+
+        self.remove_bytes(case_corrected.as_bytes())
+    }
 
-            let upgraded_node = self.root.as_mut().map_or_else(
-                || Box::new(Node0::new()).add(case_corrected.as_bytes()),
-                |v| v.add(case_corrected.as_bytes()),
-            );
-            if upgraded_node.is_some() {
-                self.root = upgraded_node;
+    //byte keys are kept exactly as given, case only applies to the &str methods
+    //any bytes are allowed, including the empty key which marks the root terminal
+    pub fn insert_bytes(&mut self, key: &[u8]) {
+        with_store!(&mut self.storage, store, root => {
+            let root = root.get_or_insert_with(|| store.leaf());
+            store.add(root, key);
+        });
+        if let Some(substrings) = self.substrings.as_mut() {
+            substrings.insert(key);
+        }
+    }
+
+    //returns whether the key was in the tree
+    pub fn remove_bytes(&mut self, key: &[u8]) -> bool {
+        let removed = with_store!(&mut self.storage, store, root => {
+            match root.as_mut() {
+                Some(node) => store.remove(node, key),
+                None => false,
             }
+        });
+        if let (true, Some(substrings)) = (removed, self.substrings.as_mut()) {
+            substrings.remove(key);
+        }
+        removed
+    }
+
+    pub fn contains_bytes(&self, key: &[u8]) -> bool {
+        with_store!(&self.storage, store, root => {
+            root.as_ref().is_some_and(|node| store.exists(node, key))
+        })
+    }
+
+    //every key starting with prefix, in byte order
+    pub fn prefix_bytes<'a>(&'a self, prefix: &[u8]) -> impl Iterator<Item = Vec<u8>> + 'a {
+        let values: Box<dyn Iterator<Item = Vec<u8>> + 'a> = with_store!(&self.storage, store, root => {
+            let node = root.as_ref().and_then(|root| store.find(root, prefix));
+            Box::new(Values::new(store, node, prefix))
+        });
+        values
+    }
+
+    //values ending with suffix, in order
+    //PrefixPostfix trees look them up in their substring index, other trees check every value
+    pub fn ends_with(&self, suffix: &str) -> Vec<String> {
+        let case_corrected = self.case.corrected(suffix);
+
+        self.suffix_bytes(case_corrected.as_bytes()).into_iter().map(lossy).collect()
+    }
+
+    pub fn suffix_bytes(&self, suffix: &[u8]) -> Vec<Vec<u8>> {
+        match self.substrings.as_ref() {
+            Some(substrings) => substrings.ends_with(suffix),
+            None => self.iter_bytes().filter(|key| key.ends_with(suffix)).collect(),
+        }
+    }
+
+    //values holding infix anywhere in them, in order, like ends_with only PrefixPostfix trees
+    //avoid checking every value
+    pub fn contains(&self, infix: &str) -> Vec<String> {
+        let case_corrected = self.case.corrected(infix);
+
+        self.infix_bytes(case_corrected.as_bytes()).into_iter().map(lossy).collect()
+    }
+
+    pub fn infix_bytes(&self, infix: &[u8]) -> Vec<Vec<u8>> {
+        match self.substrings.as_ref() {
+            Some(substrings) if !infix.is_empty() => substrings.contains(infix),
+            _ => self.iter_bytes().filter(|key| infix.is_empty() || key.windows(infix.len()).any(|window| window == infix)).collect(),
+        }
+    }
+
+    //every key in byte order
+    pub fn iter_bytes(&self) -> impl Iterator<Item = Vec<u8>> + '_ {
+        self.prefix_bytes(&[])
+    }
+
+    //immutable copy of the current values that later changes to this tree don't affect
+    //O(1), the snapshot shares every node and this tree copies one only when changing it
+    //arena nodes can't be shared so arena trees give an error instead of a full copy
+    pub fn snapshot(&self) -> Result<PersistentTree, SnapshotError> {
+        match &self.storage {
+            Storage::Heap(_, root) => Ok(PersistentTree::from_root(self.case, root.clone())),
+            Storage::Arena(..) => Err(SnapshotError),
         }
     }
 
     pub fn exists(&self, value: &str) -> bool {
-        let case_corrected = match self.case {
-            Case::Insensitve => value.to_lowercase(),
-            Case::Sensitive => String::from(value),
+        let case_corrected = self.case.corrected(value);
+
+        self.contains_bytes(case_corrected.as_bytes())
+    }
+
+    //longest stored value that input starts with, e.g. the most specific route for a url
+    pub fn longest_prefix_of(&self, input: &str) -> Option<String> {
+        let case_corrected = self.case.corrected(input);
+
+        self.longest_prefix_of_bytes(case_corrected.as_bytes()).map(|prefix| lossy(prefix.to_vec()))
+    }
+
+    //every stored value that input starts with, shortest first
+    pub fn prefixes_of(&self, input: &str) -> Vec<String> {
+        let case_corrected = self.case.corrected(input);
+
+        self.prefixes_of_bytes(case_corrected.as_bytes())
+            .into_iter()
+            .map(|prefix| lossy(prefix.to_vec()))
+            .collect()
+    }
+
+    pub fn longest_prefix_of_bytes<'i>(&self, input: &'i [u8]) -> Option<&'i [u8]> {
+        let mut longest = None;
+        with_store!(&self.storage, store, root => {
+            if let Some(root) = root.as_ref() {
+                store.prefixes(root, input, |length| longest = Some(length));
+            }
+        });
+        longest.map(|length| &input[..length])
+    }
+
+    pub fn prefixes_of_bytes<'i>(&self, input: &'i [u8]) -> Vec<&'i [u8]> {
+        let mut prefixes = Vec::new();
+        with_store!(&self.storage, store, root => {
+            if let Some(root) = root.as_ref() {
+                store.prefixes(root, input, |length| prefixes.push(&input[..length]));
+            }
+        });
+        prefixes
+    }
+
+    //follows input down from the root and at each depth d along the way looks off to the side
+    //for the bytes branches(d) lists, giving the first key down each side branch found, in the
+    //order they were found
+    //for keys that leave input at the end, such as a RoutingTable's with their closing byte
+    pub(crate) fn branches_along(&self, input: &[u8], branches: impl Fn(usize) -> Vec<u8>) -> Vec<Vec<u8>> {
+        let mut found = Vec::new();
+        with_store!(&self.storage, store, root => {
+            let mut node = root.as_ref().map(|root| store.get(root));
+            let mut depth = 0;
+            while let Some(current) = node {
+                for byte in branches(depth) {
+                    if let NodeLocation::Exists(index) = current.get_index(byte) {
+                        let mut side = input[..depth].to_vec();
+                        side.push(byte);
+                        let child = current.get_child(index.child).map(|child| store.get(child));
+                        found.extend(Values::new(store, child, &side).next());
+                    }
+                }
+                node = match input.get(depth).map(|byte| current.get_index(*byte)) {
+                    Some(NodeLocation::Exists(index)) => current.get_child(index.child).map(|child| store.get(child)),
+                    _ => None,
+                };
+                depth += 1;
+            }
+        });
+        found
+    }
+
+    //up to results values starting with value, in order
+    pub fn auto_complete(&self, value: &str, results: usize) -> Vec<String> {
+        let case_corrected = self.case.corrected(value);
+
+        self.prefix_bytes(case_corrected.as_bytes())
+            .take(results)
+            .map(lossy)
+            .collect()
+    }
+
+    //how far prefix extends before values branch or one ends, like tab completion in a shell
+    //the extension stops short of a character that isn't decided yet, so a branch between
+    //"é" and "ê" gives nothing even though both start with the same byte
+    pub fn common_extension(&self, prefix: &str) -> String {
+        let case_corrected = self.case.corrected(prefix);
+
+        let mut extension = self.common_extension_bytes(case_corrected.as_bytes());
+        let complete = match std::str::from_utf8(&extension) {
+            Ok(_) => extension.len(),
+            Err(e) => e.valid_up_to(),
         };
+        extension.truncate(complete);
+        String::from_utf8(extension).unwrap()
+    }
+
+    //characters values starting with prefix continue with, in order
+    //for completion menus offering one character at a time, a multi byte character is offered whole
+    pub fn next_chars(&self, prefix: &str) -> Vec<char> {
+        let case_corrected = self.case.corrected(prefix);
+
+        with_store!(&self.storage, store, root => {
+            match root.as_ref().and_then(|root| store.find(root, case_corrected.as_bytes())) {
+                Some(node) => char_children(store, node).into_iter().map(|(c, _, _)| c).collect(),
+                None => Vec::new(),
+            }
+        })
+    }
+
+    //bytes every key starting with prefix continues with, empty when none do
+    pub fn common_extension_bytes(&self, prefix: &[u8]) -> Vec<u8> {
+        let mut extension = Vec::new();
+        with_store!(&self.storage, store, root => {
+            let mut node = root.as_ref().and_then(|root| store.find(root, prefix));
+            while let Some(current) = node.filter(|node| !node.is_terminal()) {
+                node = match current.children().as_slice() {
+                    [(key, child)] => {
+                        extension.push(*key);
+                        Some(store.get(*child))
+                    }
+                    _ => None,
+                };
+            }
+        });
+        extension
+    }
+
+    //shell style completion of the segment prefix ends in, up to and including the next
+    //separator, with the number of values under each completion
+    //e.g. "src/trie/" gives ("nodes/", 14) and ("tree.rs", 1) for paths split on '/'
+    //counting walks every node under prefix, use next_segments when only the completions matter
+    pub fn complete_segment(&self, prefix: &str, separator: char) -> Vec<(String, usize)> {
+        self.segments(prefix, separator, true)
+    }
+
+    //the completions of complete_segment without their counts, only walks the nodes between
+    //prefix and the next separator
+    pub fn next_segments(&self, prefix: &str, separator: char) -> Vec<String> {
+        self.segments(prefix, separator, false).into_iter().map(|(segment, _)| segment).collect()
+    }
+
+    fn segments(&self, prefix: &str, separator: char, counted: bool) -> Vec<(String, usize)> {
+        let case_corrected = self.case.corrected(prefix);
+
+        let segment_start = case_corrected.rfind(separator).map_or(0, |i| i + separator.len_utf8());
+        let segment = case_corrected.as_bytes()[segment_start..].to_vec();
+        let separator = separator.to_string();
+        with_store!(&self.storage, store, root => {
+            match root.as_ref().and_then(|root| store.find(root, case_corrected.as_bytes())) {
+                Some(node) => segments(store, node, segment, separator.as_bytes(), counted)
+                    .into_iter()
+                    .map(|(segment, values)| (lossy(segment), values))
+                    .collect(),
+                None => Vec::new(),
+            }
+        })
+    }
+
+    //values matching pattern in order, ? stands for exactly one character of any byte length
+    //"c?t" finds "cat" and "cœt" but not "ct" or "cart"
+    pub fn wildcard(&self, pattern: &str) -> Vec<String> {
+        let case_corrected = self.case.corrected(pattern);
+
+        let pattern = case_corrected.chars().map(|c| Some(c).filter(|&c| c != '?')).collect::<Vec<_>>();
+        with_store!(&self.storage, store, root => match root.as_ref() {
+            Some(root) => wildcard(store, store.get(root), &pattern).into_iter().map(lossy).collect(),
+            None => Vec::new(),
+        })
+    }
+
+    //byte range in text of each value occurring in it, ordered by start then end
+    //builds a matcher every call, use matcher to scan many texts
+    //"he", "she" and "hers" in "ushers" give 1..4, 2..4 and 2..6 overlapping, and 1..4 leftmost longest
+    pub fn find_all_in(&self, text: &str, occurrences: Occurrences) -> Vec<Range<usize>> {
+        self.matcher().find_all_in(text, occurrences)
+    }
+
+    pub fn find_all_in_bytes(&self, text: &[u8], occurrences: Occurrences) -> Vec<Range<usize>> {
+        self.matcher().find_all_in_bytes(text, occurrences)
+    }
 
-        if let Some(node) = self.root.as_ref() {
-            node.exists(case_corrected.as_bytes())
+    //automaton finding every value in a text in one pass over it
+    pub fn matcher(&self) -> Matcher {
+        let automaton = with_store!(&self.storage, store, root => Automaton::new(store, root.as_ref().map(|root| store.get(root))));
+        Matcher::new(self.case, automaton)
+    }
+
+    //values within max_distance character edits of value, in order
+    //"é" is one edit away from "e" even though their bytes differ by two
+    pub fn fuzzy(&self, value: &str, max_distance: usize) -> Vec<String> {
+        let case_corrected = self.case.corrected(value);
+
+        let query = case_corrected.chars().collect::<Vec<_>>();
+        with_store!(&self.storage, store, root => match root.as_ref() {
+            Some(root) => fuzzy(store, store.get(root), &query, max_distance)
+                .into_iter()
+                .map(lossy)
+                .collect(),
+            None => Vec::new(),
+        })
+    }
+
+    pub fn stats(&self) -> Stats {
+        with_store!(&self.storage, store, root => match root.as_ref() {
+            Some(root) => Stats::collect(store, store.get(root)),
+            None => Stats::default(),
+        })
+    }
+
+    //checks every node's layout against the invariants lookups rely on
+    //every broken invariant is reported, parents before their children
+    pub fn validate(&self) -> Result<(), Vec<InvalidNode>> {
+        let invalid = with_store!(&self.storage, store, root => match root.as_ref() {
+            Some(root) => validate(store, store.get(root)),
+            None => Vec::new(),
+        });
+        if invalid.is_empty() {
+            Ok(())
         } else {
-            false
+            Err(invalid)
+        }
+    }
+
+    //graphviz digraph of the nodes down to max_depth, render with `dot -Tsvg`
+    pub fn to_dot(&self, max_depth: usize) -> String {
+        with_store!(&self.storage, store, root => match root.as_ref() {
+            Some(root) => dot(store, store.get(root), max_depth),
+            None => String::from("digraph tree {\n}\n"),
+        })
+    }
+
+    //the root and then the node reached by each byte of value, in order
+    //stops early at the first byte that has no child, so a value that isn't in the tree
+    //gives fewer than value.len() + 1 nodes
+    pub fn path(&self, value: &str) -> Vec<PathNode> {
+        let case_corrected = self.case.corrected(value);
+
+        with_store!(&self.storage, store, root => {
+            let mut path = Vec::new();
+            let mut next = root.as_ref().map(|root| (None, store.get(root)));
+            let mut bytes = case_corrected.bytes();
+            while let Some((key, node)) = next {
+                path.push(PathNode::of(key, node));
+                next = bytes.next().and_then(|byte| match node.get_index(byte) {
+                    NodeLocation::Exists(index) => Some((Some(byte), store.get(node.get_child(index.child)?))),
+                    _ => None,
+                });
+            }
+            path
+        })
+    }
+
+    //settings and values, node types and allocation aren't kept
+    pub fn save<W: Write>(&self, mut out: W) -> io::Result<()> {
+        let case = match self.case {
+            Case::Sensitive => 0,
+            Case::Insensitve => 1,
+        };
+        let matching = match self.matching {
+            Match::Exact => 0,
+            Match::Prefix => 1,
+            Match::PrefixPostfix => 2,
+        };
+        out.write_all(MAGIC)?;
+        out.write_all(&[case, matching, self.compress as u8])?;
+        with_store!(&self.storage, store, root => match root.as_ref() {
+            Some(root) => {
+                out.write_all(&[1])?;
+                serialize::write(store, store.get(root), &mut out)?;
+            }
+            None => out.write_all(&[0])?,
+        });
+        out.flush()
+    }
+
+    //files nesting deeper than serialize::MAX_DEPTH or holding malformed nodes are refused
+    pub fn load<R: Read>(input: R, allocation: Allocation) -> io::Result<Self> {
+        Tree::read(input, allocation).map_err(|e| match e.kind() {
+            io::ErrorKind::UnexpectedEof => invalid("saved tree is truncated"),
+            _ => e,
+        })
+    }
+
+    fn read<R: Read>(mut input: R, allocation: Allocation) -> io::Result<Self> {
+        let mut header = [0; 9];
+        input.read_exact(&mut header)?;
+        if &header[..5] != MAGIC {
+            return Err(invalid("not a saved tree"));
+        }
+        let case = match header[5] {
+            0 => Case::Sensitive,
+            1 => Case::Insensitve,
+            _ => return Err(invalid("unknown case")),
+        };
+        let matching = match header[6] {
+            0 => Match::Exact,
+            1 => Match::Prefix,
+            2 => Match::PrefixPostfix,
+            _ => return Err(invalid("unknown match")),
+        };
+
+        let mut tree = Tree::with_allocation(matching, case, header[7] != 0, allocation);
+        if header[8] != 0 {
+            with_store!(&mut tree.storage, store, root => {
+                *root = Some(serialize::read(store, &mut input)?);
+            });
         }
+        tree.reindex();
+        Ok(tree)
+    }
+
+    //values within range in order, bounds are case corrected like values
+    pub fn range<'r>(&self, range: impl RangeBounds<&'r str>) -> impl Iterator<Item = String> + '_ {
+        let case_corrected = |value: &&str| self.case.corrected(value).into_owned().into_bytes();
+        let (start, end) = (range.start_bound().map(case_corrected), range.end_bound().map(case_corrected));
+        self.bounded(start, end).map(lossy)
+    }
+
+    //keys within range in byte order
+    pub fn range_bytes<'k>(&self, range: impl RangeBounds<&'k [u8]>) -> impl Iterator<Item = Vec<u8>> + '_ {
+        let owned = |bound: &&[u8]| bound.to_vec();
+        self.bounded(range.start_bound().map(owned), range.end_bound().map(owned))
     }
 
-    pub fn auto_complete(&self, value: &str, results :usize) -> Vec<String> {
-        //search to value.last and then enumerate all
-        todo!()
+    //bounds are owned so the iterator doesn't borrow from the caller's range
+    pub(crate) fn bounded(&self, start: Bound<Vec<u8>>, end: Bound<Vec<u8>>) -> impl Iterator<Item = Vec<u8>> + '_ {
+        let values: Box<dyn Iterator<Item = Vec<u8>> + '_> = with_store!(&self.storage, store, root => {
+            let node = root.as_ref().map(|root| store.get(root));
+            match &start {
+                Bound::Included(start) | Bound::Excluded(start) => Box::new(Values::seek(store, node, start)),
+                Bound::Unbounded => Box::new(Values::new(store, node, &[])),
+            }
+        });
+        values
+            .skip_while(move |value| matches!(&start, Bound::Excluded(start) if start == value))
+            .take_while(move |value| match &end {
+                Bound::Included(end) => value <= end,
+                Bound::Excluded(end) => value < end,
+                Bound::Unbounded => true,
+            })
     }
+
+    //every value in order
+    pub fn iter(&self) -> impl Iterator<Item = String> + '_ {
+        self.iter_bytes().map(lossy)
+    }
+}
+
+//values from the &str methods are always utf-8, keys added with insert_bytes may not be
+//and come out with invalid sequences replaced, the byte methods return them unchanged
+pub(crate) fn lossy(value: Vec<u8>) -> String {
+    String::from_utf8(value).unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned())
 }
 
 #[cfg(test)] //module should only be compiled for testing
 mod test {
-    use super::{Case, Match, Tree};
+    use super::{Allocation, Case, Match, Occurrences, SnapshotError, Storage, Tree};
+    use crate::trie::enums::NodeKind;
+    use std::ops::Bound;
+    use crate::trie::errors::UnsortedError;
+    use crate::trie::nodes::node::NodeLocation::Exists;
+    use crate::trie::nodes::node::NodeRef;
+    use crate::trie::nodes::serialize::MAX_DEPTH;
+    use crate::trie::nodes::store::Store;
     use std::fs::File;
     use std::io;
     use std::io::{BufRead, BufReader, Lines};
@@ -106,6 +694,651 @@ mod test {
         assert!(!tree.exists("bodiess"));
     }
 
+    #[test]
+    fn exists_only_for_added_words() {
+        for allocation in [Allocation::Heap, Allocation::Arena] {
+            let mut tree =
+                Tree::with_allocation(Match::Exact, Case::Sensitive, false, allocation);
+            for word in &["a", "ab", "abc", "abd", "b", "bcd"] {
+                tree.add(word);
+            }
+
+            for word in &["a", "ab", "abc", "abd", "b", "bcd"] {
+                assert!(tree.exists(word));
+            }
+            for word in &["", "abcd", "bc", "c", "A"] {
+                assert!(!tree.exists(word));
+            }
+        }
+    }
+
+    //sorted words whose prefixes fan out into every node type
+    fn sorted_words() -> Vec<String> {
+        let mut words = Vec::new();
+        for first in 0..60u8 {
+            let first = (b'0' + first) as char;
+            words.push(first.to_string());
+            for second in 0..(first as u8 % 50) {
+                words.push(format!("{}{}", first, (b'0' + second) as char));
+                words.push(format!("{}{}zz", first, (b'0' + second) as char));
+            }
+        }
+        words.sort();
+        words
+    }
+
+    #[test]
+    fn from_sorted_iter_matches_sequential_adds() {
+        let words = sorted_words();
+        let bulk =
+            Tree::from_sorted_iter(Match::Exact, Case::Sensitive, false, Allocation::Heap, &words)
+                .unwrap();
+        let mut sequential = Tree::new(Match::Exact, Case::Sensitive, false);
+        for word in &words {
+            sequential.add(word);
+        }
+
+        //node types, key order and sizes all come out the same
+        match (&bulk.storage, &sequential.storage) {
+            (Storage::Heap(_, bulk), Storage::Heap(_, sequential)) => {
+                assert_eq!(format!("{:?}", bulk), format!("{:?}", sequential))
+            }
+            _ => panic!(),
+        }
+
+        let arena =
+            Tree::from_sorted_iter(Match::Exact, Case::Sensitive, false, Allocation::Arena, &words)
+                .unwrap();
+        for word in &words {
+            assert!(arena.exists(word));
+            assert!(!arena.exists(&format!("{}z", word)));
+        }
+    }
+
+    #[test]
+    fn from_sorted_iter_rejects_unsorted_input() {
+        let load = |case, words: &[&str]| {
+            Tree::from_sorted_iter(Match::Exact, case, false, Allocation::Heap, words)
+        };
+
+        let error = load(Case::Sensitive, &["a", "b", "b", "bc", "ba"]).err();
+        assert_eq!(error, Some(UnsortedError { position: 4, value: String::from("ba") }));
+
+        //order is checked after case correction
+        assert!(load(Case::Sensitive, &["a", "B", "c"]).is_err());
+        assert!(load(Case::Insensitve, &["a", "B", "c"]).unwrap().exists("b"));
+    }
+
+    fn kind<L>(node: NodeRef<L>) -> usize {
+        match node {
+            NodeRef::N0(_) => 0,
+            NodeRef::N4(_) => 4,
+            NodeRef::N16(_) => 16,
+            NodeRef::N48(_) => 48,
+            NodeRef::N256(_) => 256,
+        }
+    }
+
+    //same node types, terminals and keys at every level
+    //slot order is ignored since Node4 and Node48 keep keys in insertion order
+    fn same_shape<A: Store, B: Store>(a: &A, a_link: &A::Link, b: &B, b_link: &B::Link) -> bool {
+        let (a_node, b_node) = (a.get(a_link), b.get(b_link));
+        if kind(a_node) != kind(b_node) || a_node.is_terminal() != b_node.is_terminal() {
+            return false;
+        }
+        (0..=255).all(|key| match (a_node.get_index(key), b_node.get_index(key)) {
+            (Exists(a_index), Exists(b_index)) => {
+                match (a_node.get_child(a_index.child), b_node.get_child(b_index.child)) {
+                    (Some(a_child), Some(b_child)) => same_shape(a, a_child, b, b_child),
+                    (a_child, b_child) => a_child.is_none() && b_child.is_none(),
+                }
+            }
+            (Exists(_), _) | (_, Exists(_)) => false,
+            _ => true,
+        })
+    }
+
+    fn same_tree(a: &Tree, b: &Tree) -> bool {
+        match (&a.storage, &b.storage) {
+            (Storage::Heap(a, Some(a_root)), Storage::Heap(b, Some(b_root))) => {
+                same_shape(a, a_root, b, b_root)
+            }
+            (Storage::Arena(a, Some(a_root)), Storage::Heap(b, Some(b_root))) => {
+                same_shape(a, a_root, b, b_root)
+            }
+            (Storage::Heap(_, None), Storage::Heap(_, None)) => true,
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn par_build_matches_sequential_adds() {
+        //reverse and interleave so no worker sees its values in order
+        let mut words = sorted_words();
+        words.reverse();
+        let (even, odd): (Vec<_>, Vec<_>) = words.iter().enumerate().partition(|(i, _)| i % 2 == 0);
+        let words = odd.into_iter().chain(even).map(|(_, word)| word.clone()).collect::<Vec<_>>();
+
+        let mut sequential = Tree::new(Match::Exact, Case::Sensitive, false);
+        for word in &words {
+            sequential.add(word);
+        }
+
+        for allocation in [Allocation::Heap, Allocation::Arena] {
+            for workers in [1, 4] {
+                let parallel = Tree::par_build_with(
+                    Match::Exact,
+                    Case::Sensitive,
+                    false,
+                    allocation,
+                    &words,
+                    workers,
+                );
+                assert!(same_tree(&parallel, &sequential));
+                for word in &words {
+                    assert!(parallel.exists(word));
+                    assert!(!parallel.exists(&format!("{}z", word)));
+                }
+            }
+        }
+
+        let empty = Tree::par_build(Match::Exact, Case::Sensitive, false, Allocation::Heap, [""]);
+        assert!(same_tree(&empty, &Tree::new(Match::Exact, Case::Sensitive, false)));
+    }
+
+    #[test]
+    fn iter_and_auto_complete_are_ordered() {
+        let words = sorted_words();
+        for allocation in [Allocation::Heap, Allocation::Arena] {
+            let mut tree = Tree::with_allocation(Match::Exact, Case::Sensitive, false, allocation);
+            assert_eq!(tree.iter().count(), 0);
+            assert!(tree.auto_complete("a", 5).is_empty());
+            for word in words.iter().rev() {
+                tree.add(word);
+            }
+
+            assert_eq!(tree.iter().collect::<Vec<_>>(), words);
+            let expected = words.iter().filter(|word| word.starts_with('5')).take(7).collect::<Vec<_>>();
+            assert_eq!(tree.auto_complete("5", 7).iter().collect::<Vec<_>>(), expected);
+            assert_eq!(tree.auto_complete("50zz", 3), vec!["50zz"]);
+            assert!(tree.auto_complete("50zzz", 3).is_empty());
+            assert_eq!(tree.auto_complete("", 2), &words[..2]);
+        }
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let words = sorted_words();
+        let mut tree = Tree::new(Match::Prefix, Case::Insensitve, false);
+        for word in &words {
+            tree.add(word);
+        }
+        let mut saved = Vec::new();
+        tree.save(&mut saved).unwrap();
+        let mut lowercase = words.iter().map(|word| word.to_lowercase()).collect::<Vec<_>>();
+        lowercase.sort();
+        lowercase.dedup();
+
+        for allocation in [Allocation::Heap, Allocation::Arena] {
+            let loaded = Tree::load(saved.as_slice(), allocation).unwrap();
+            assert_eq!(loaded.iter().collect::<Vec<_>>(), lowercase);
+            assert!(loaded.exists("A0"));
+            assert!(matches!(loaded.matching, Match::Prefix));
+            let (stats, loaded_stats) = (tree.stats(), loaded.stats());
+            assert_eq!(loaded_stats.keys, stats.keys);
+            assert_eq!(loaded_stats.nodes(), stats.nodes());
+        }
+
+        let mut empty = Vec::new();
+        Tree::new(Match::Exact, Case::Sensitive, false).save(&mut empty).unwrap();
+        assert_eq!(Tree::load(empty.as_slice(), Allocation::Heap).unwrap().iter().count(), 0);
+
+        assert!(Tree::load(&b"ARST"[..], Allocation::Heap).is_err());
+        assert!(Tree::load(&saved[..saved.len() - 1], Allocation::Heap).is_err());
+        //a child count past the end of the input
+        let mut corrupt = saved.clone();
+        corrupt[10] = 0xff;
+        assert!(Tree::load(corrupt.as_slice(), Allocation::Heap).is_err());
+    }
+
+    #[test]
+    fn load_rejects_nesting_past_the_longest_key() {
+        //a chain of single child nodes, each level only four bytes of input
+        let chain = |levels: usize| {
+            let mut saved = b"ARST\x01\x00\x00\x00\x01".to_vec();
+            for _ in 0..levels {
+                saved.extend_from_slice(&[0, 1, 0, b'a']);
+            }
+            saved.extend_from_slice(&[1, 0, 0]);
+            saved
+        };
+        for allocation in [Allocation::Heap, Allocation::Arena] {
+            let error = Tree::load(chain(1_000_000).as_slice(), allocation).err().unwrap();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+            let loaded = Tree::load(chain(MAX_DEPTH).as_slice(), allocation).unwrap();
+            assert!(loaded.exists(&"a".repeat(MAX_DEPTH)));
+        }
+
+        //a longer key is saved but not loaded back
+        let tree = Tree::from_sorted_iter(Match::Exact, Case::Sensitive, false, Allocation::Heap, ["a".repeat(MAX_DEPTH + 1)]).unwrap();
+        let mut saved = Vec::new();
+        tree.save(&mut saved).unwrap();
+        assert_eq!(Tree::load(saved.as_slice(), Allocation::Heap).err().unwrap().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn load_rejects_leaves_without_a_value() {
+        //root with a child "a" that has neither children nor a value
+        let saved = b"ARST\x01\x00\x00\x00\x01\x00\x01\x00a\x00\x00\x00";
+        for allocation in [Allocation::Heap, Allocation::Arena] {
+            let error = Tree::load(&saved[..], allocation).err().unwrap();
+            assert_eq!(error.to_string(), "leaf is neither terminal nor the root");
+        }
+        //the empty root of an empty tree is fine
+        let saved = b"ARST\x01\x00\x00\x00\x01\x00\x00\x00";
+        assert!(Tree::load(&saved[..], Allocation::Heap).unwrap().iter().next().is_none());
+    }
+
+    #[test]
+    fn fuzzy_finds_words_within_distance() {
+        let mut tree = Tree::new(Match::Exact, Case::Sensitive, false);
+        for word in &["cat", "cart", "cast", "at", "dog", "scat"] {
+            tree.add(word);
+        }
+
+        assert_eq!(tree.fuzzy("cat", 0), vec!["cat"]);
+        assert_eq!(tree.fuzzy("cat", 1), vec!["at", "cart", "cast", "cat", "scat"]);
+        assert_eq!(tree.fuzzy("dot", 1), vec!["dog"]);
+        assert!(tree.fuzzy("xyz", 2).is_empty());
+    }
+
+    #[test]
+    fn stats_count_nodes_by_type() {
+        let mut tree = Tree::new(Match::Exact, Case::Sensitive, false);
+        assert_eq!(tree.stats().nodes(), 0);
+        for word in &["a", "ab", "ac", "b"] {
+            tree.add(word);
+        }
+
+        let stats = tree.stats();
+        assert_eq!(stats.keys, 4);
+        assert_eq!((stats.node0.count, stats.node4.count), (3, 2));
+        assert_eq!(stats.max_depth, 2);
+        assert_eq!(stats.depths, vec![1, 2, 2]);
+        assert_eq!(stats.average_key_length(), 1.5);
+        assert_eq!((stats.node4.children, stats.node4.slots), (4, 8));
+        assert_eq!(stats.node4.fill(), 0.5);
+        assert_eq!(stats.wasted_slots(), 4);
+        assert!(stats.node4.bytes > stats.node0.bytes);
+        assert_eq!(stats.bytes, stats.node0.bytes + stats.node4.bytes);
+
+        for word in (0..20u8).map(|i| ((b'c' + i) as char).to_string()) {
+            tree.add(&word);
+        }
+        let stats = tree.stats();
+        assert_eq!((stats.node16.count, stats.node48.count), (0, 1));
+        assert_eq!(stats.node48.wasted_slots(), 48 - 22);
+    }
+
+    #[test]
+    fn remove_keeps_other_words() {
+        for allocation in [Allocation::Heap, Allocation::Arena] {
+            let mut tree = Tree::with_allocation(Match::Exact, Case::Sensitive, false, allocation);
+            let words = sorted_words();
+            for word in &words {
+                tree.add(word);
+            }
+
+            let (removed, kept): (Vec<_>, Vec<_>) = words.iter().partition(|word| word.len() % 3 != 1);
+            for word in &removed {
+                assert!(tree.remove(word));
+                assert!(!tree.remove(word));
+            }
+            assert!(!tree.remove("not there"));
+
+            for word in &removed {
+                assert!(!tree.exists(word));
+            }
+            for word in &kept {
+                assert!(tree.exists(word));
+            }
+        }
+    }
+
+    #[test]
+    fn every_mutation_leaves_a_valid_tree() {
+        for allocation in [Allocation::Heap, Allocation::Arena] {
+            let mut tree = Tree::with_allocation(Match::Exact, Case::Sensitive, false, allocation);
+            //enough children under "a" to pass through every node type on the way up and down
+            let words = (1..128u8).map(|byte| format!("a{}", byte as char)).collect::<Vec<_>>();
+            for word in &words {
+                tree.add(word);
+                assert_eq!(tree.validate(), Ok(()));
+            }
+            for word in words.iter().step_by(2).chain(words.iter().skip(1).step_by(2)) {
+                tree.remove(word);
+                assert_eq!(tree.validate(), Ok(()));
+            }
+
+            let words = sorted_words();
+            let bulk = Tree::from_sorted_iter(Match::Exact, Case::Sensitive, false, allocation, &words).unwrap();
+            assert_eq!(bulk.validate(), Ok(()));
+            let parallel = Tree::par_build_with(Match::Exact, Case::Sensitive, false, allocation, &words, 4);
+            assert_eq!(parallel.validate(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn byte_keys_take_any_bytes_and_the_empty_key() {
+        for allocation in [Allocation::Heap, Allocation::Arena] {
+            let mut tree = Tree::with_allocation(Match::Exact, Case::Insensitve, false, allocation);
+            let keys: [&[u8]; 5] = [b"\xff\x00", b"", b"\xff", b"AB", b"\x00"];
+            for key in &keys {
+                tree.insert_bytes(key);
+            }
+
+            //kept as given, case correction only applies to &str
+            assert!(tree.contains_bytes(b"AB") && !tree.contains_bytes(b"ab"));
+            assert!(tree.contains_bytes(b"") && tree.exists(""));
+            assert!(!tree.contains_bytes(b"\xff\x00\x00"));
+            let mut sorted = keys.iter().map(|key| key.to_vec()).collect::<Vec<_>>();
+            sorted.sort();
+            assert_eq!(tree.iter_bytes().collect::<Vec<_>>(), sorted);
+            assert_eq!(tree.prefix_bytes(b"\xff").collect::<Vec<_>>(), vec![b"\xff".to_vec(), b"\xff\x00".to_vec()]);
+            assert_eq!(tree.range_bytes(&b"\x01"[..]..&b"\xff"[..]).collect::<Vec<_>>(), vec![b"AB".to_vec()]);
+            assert_eq!(tree.iter().next().as_deref(), Some(""));
+            assert_eq!(tree.iter().last().as_deref(), Some("\u{fffd}\0"));
+
+            let mut saved = Vec::new();
+            tree.save(&mut saved).unwrap();
+            let loaded = Tree::load(&saved[..], allocation).unwrap();
+            assert_eq!(loaded.iter_bytes().collect::<Vec<_>>(), sorted);
+
+            assert!(tree.remove_bytes(b""));
+            assert!(!tree.remove_bytes(b""));
+            assert!(!tree.contains_bytes(b"") && tree.contains_bytes(b"\x00"));
+            assert!(tree.remove_bytes(b"\xff"));
+            assert!(tree.contains_bytes(b"\xff\x00"));
+            assert_eq!(tree.validate(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn finds_stored_prefixes_of_the_input() {
+        for allocation in [Allocation::Heap, Allocation::Arena] {
+            let mut tree = Tree::with_allocation(Match::Exact, Case::Insensitve, false, allocation);
+            assert_eq!(tree.longest_prefix_of("/api"), None);
+            for route in &["/", "/api", "/api/users", "/api/users/admin", "/static"] {
+                tree.add(route);
+            }
+
+            assert_eq!(tree.longest_prefix_of("/API/users/42").as_deref(), Some("/api/users"));
+            assert_eq!(tree.longest_prefix_of("/api/user").as_deref(), Some("/api"));
+            assert_eq!(tree.longest_prefix_of("/static").as_deref(), Some("/static"));
+            assert_eq!(tree.longest_prefix_of("api"), None);
+            assert_eq!(tree.prefixes_of("/api/users/admin/x"), vec!["/", "/api", "/api/users", "/api/users/admin"]);
+            assert!(tree.prefixes_of("").is_empty());
+
+            //the empty key is a prefix of everything
+            tree.insert_bytes(b"");
+            assert_eq!(tree.prefixes_of_bytes(b"/s"), vec![&b""[..], &b"/"[..]]);
+            assert_eq!(tree.longest_prefix_of_bytes(b"x"), Some(&b""[..]));
+        }
+    }
+
+    #[test]
+    fn common_extension_stops_where_values_branch() {
+        for allocation in [Allocation::Heap, Allocation::Arena] {
+            let mut tree = Tree::with_allocation(Match::Exact, Case::Insensitve, false, allocation);
+            for word in &["interstellar", "internet", "internets", "café", "cafés", "naïve", "naïf", "résumé"] {
+                tree.add(word);
+            }
+
+            assert_eq!(tree.common_extension("INT"), "er");
+            assert_eq!(tree.common_extension("interne"), "t");
+            //stops at a value even with one way to go on
+            assert_eq!(tree.common_extension("internet"), "");
+            assert_eq!(tree.common_extension("caf"), "é");
+            assert_eq!(tree.common_extension("r"), "ésumé");
+            //"naïve" and "naïf" only differ after the ï
+            assert_eq!(tree.common_extension("na"), "ï");
+            assert_eq!(tree.common_extension("x"), "");
+            //"é" and "ê" share their first byte, which isn't a character on its own
+            tree.add("rêve");
+            assert_eq!(tree.common_extension("r"), "");
+            assert_eq!(tree.common_extension_bytes("r".as_bytes()), vec![0xc3]);
+        }
+    }
+
+    #[test]
+    fn character_searches_never_split_a_character() {
+        for allocation in [Allocation::Heap, Allocation::Arena] {
+            let mut tree = Tree::with_allocation(Match::Exact, Case::Sensitive, false, allocation);
+            for word in &["cat", "cät", "cœt", "c😀t", "ct", "cart", "rêve", "réve", "reve"] {
+                tree.add(word);
+            }
+
+            //one ? stands for one character whatever its length in bytes
+            assert_eq!(tree.wildcard("c?t"), vec!["cat", "cät", "cœt", "c😀t"]);
+            assert_eq!(tree.wildcard("c??t"), vec!["cart"]);
+            assert_eq!(tree.wildcard("r?ve"), vec!["reve", "réve", "rêve"]);
+            assert_eq!(tree.wildcard("ct"), vec!["ct"]);
+            assert_eq!(tree.wildcard("c?"), vec!["ct"]);
+
+            //substituting é for e is one edit, not the two it would be counting bytes
+            assert_eq!(tree.fuzzy("reve", 1), vec!["reve", "réve", "rêve"]);
+            assert_eq!(tree.fuzzy("c😀t", 0), vec!["c😀t"]);
+            assert_eq!(tree.fuzzy("ct", 1), vec!["cat", "ct", "cät", "cœt", "c😀t"]);
+
+            assert_eq!(tree.next_chars("r"), vec!['e', 'é', 'ê']);
+            assert_eq!(tree.next_chars("c"), vec!['a', 't', 'ä', 'œ', '😀']);
+            assert!(tree.next_chars("x").is_empty());
+        }
+    }
+
+    #[test]
+    fn finds_values_by_suffix_and_infix() {
+        for matching in [Match::Exact, Match::PrefixPostfix] {
+            let mut tree = Tree::new(matching, Case::Insensitve, false);
+            for word in &["Running", "sing", "singer", "ring", "in", "bandana", "banana", "rhinoceros"] {
+                tree.add(word);
+            }
+
+            assert_eq!(tree.ends_with("ING"), vec!["ring", "running", "sing"]);
+            assert_eq!(tree.ends_with("ana"), vec!["banana", "bandana"]);
+            assert_eq!(tree.contains("in"), vec!["in", "rhinoceros", "ring", "running", "sing", "singer"]);
+            assert_eq!(tree.contains("n"), vec!["banana", "bandana", "in", "rhinoceros", "ring", "running", "sing", "singer"]);
+            //longer than an n-gram, every gram is in bandana but not in that order
+            assert_eq!(tree.contains("anana"), vec!["banana"]);
+            assert_eq!(tree.contains("nnin"), vec!["running"]);
+            assert!(tree.contains("nandana").is_empty());
+            assert_eq!(tree.contains("").len(), 8);
+
+            //removing a value drops it from both searches, the other owners of its grams stay
+            assert!(tree.remove("singer"));
+            tree.add("ringer");
+            assert_eq!(tree.contains("nge"), vec!["ringer"]);
+            assert_eq!(tree.ends_with("er"), vec!["ringer"]);
+            assert!(tree.remove("banana"));
+            assert_eq!(tree.contains("ana"), vec!["bandana"]);
+        }
+
+        //trees built in bulk or loaded index what they hold
+        let tree = Tree::from_sorted_iter(Match::PrefixPostfix, Case::Sensitive, false, Allocation::Arena, ["ab", "cab", "dabs"]).unwrap();
+        assert_eq!(tree.ends_with("ab"), vec!["ab", "cab"]);
+        let mut saved = Vec::new();
+        tree.save(&mut saved).unwrap();
+        let loaded = Tree::load(saved.as_slice(), Allocation::Heap).unwrap();
+        assert_eq!(loaded.contains("abs"), vec!["dabs"]);
+    }
+
+    #[test]
+    fn finds_every_value_inside_a_text() {
+        let mut tree = Tree::new(Match::Exact, Case::Sensitive, false);
+        for word in &["he", "she", "his", "hers"] {
+            tree.add(word);
+        }
+        let text = "ushers and his";
+        assert_eq!(tree.find_all_in(text, Occurrences::Overlapping), vec![1..4, 2..4, 2..6, 11..14]);
+        //she starts before he and hers, and hers would overlap she
+        assert_eq!(tree.find_all_in(text, Occurrences::LeftmostLongest), vec![1..4, 11..14]);
+        tree.add("shers");
+        assert_eq!(tree.find_all_in(text, Occurrences::LeftmostLongest), vec![1..6, 11..14]);
+        assert!(tree.find_all_in("", Occurrences::Overlapping).is_empty());
+        //following failure links, "ab" fails over to "b" without going back to the start
+        let mut tree = Tree::new(Match::Exact, Case::Sensitive, false);
+        tree.add("abc");
+        tree.add("bd");
+        assert_eq!(tree.find_all_in("abd", Occurrences::Overlapping), vec![1..3]);
+        assert!(Tree::new(Match::Exact, Case::Sensitive, false).find_all_in("abc", Occurrences::Overlapping).is_empty());
+    }
+
+    #[test]
+    fn find_all_in_reports_offsets_into_the_original_text() {
+        for allocation in [Allocation::Heap, Allocation::Arena] {
+            let mut tree = Tree::with_allocation(Match::Exact, Case::Insensitve, false, allocation);
+            for word in &["spam", "ΟΔΟΣ", "i"] {
+                tree.add(word);
+            }
+            let matcher = tree.matcher();
+            let text = "SPAM, Spam and ΟΔΟΣ";
+            assert_eq!(matcher.find_all_in(text, Occurrences::LeftmostLongest), vec![0..4, 6..10, 15..23]);
+            assert_eq!(&text[15..23], "ΟΔΟΣ");
+            //İ lowercases to i and a combining dot, three bytes for the two of the original
+            let text = "İ spam";
+            assert_eq!(matcher.find_all_in(text, Occurrences::Overlapping), vec![0..2, 3..7]);
+        }
+    }
+
+    #[test]
+    fn completes_one_segment_at_a_time() {
+        let mut tree = Tree::new(Match::Exact, Case::Sensitive, false);
+        let paths = [
+            "src/lib.rs",
+            "src/trie",
+            "src/trie/tree.rs",
+            "src/trie/nodes/node4.rs",
+            "src/trie/nodes/node16.rs",
+            "src/trie/nodes/mod.rs",
+            "src/trie/mod.rs",
+            "src/trie/nodes.txt",
+            "a→b→c",
+            "a→d",
+        ];
+        for path in &paths {
+            tree.add(path);
+        }
+        let complete = |prefix, separator| {
+            tree.complete_segment(prefix, separator)
+                .into_iter()
+                .map(|(segment, values)| format!("{} {}", segment, values))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(complete("src/trie/", '/'), vec!["mod.rs 1", "nodes.txt 1", "nodes/ 3", "tree.rs 1"]);
+        assert_eq!(complete("src/tr", '/'), vec!["trie 1", "trie/ 6"]);
+        assert_eq!(complete("", '/'), vec!["a→b→c 1", "a→d 1", "src/ 8"]);
+        assert_eq!(complete("src/trie/nodes/node", '/'), vec!["node16.rs 1", "node4.rs 1"]);
+        assert!(complete("lib/", '/').is_empty());
+        //any separator, including ones longer than a byte
+        assert_eq!(complete("src/trie/nodes/n", '.'), vec!["src/trie/nodes/node16. 1", "src/trie/nodes/node4. 1"]);
+        assert_eq!(complete("a→", '→'), vec!["b→ 1", "d 1"]);
+        assert_eq!(tree.next_segments("src/trie/", '/'), vec!["mod.rs", "nodes.txt", "nodes/", "tree.rs"]);
+        assert_eq!(tree.next_segments("src/tr", '/'), vec!["trie", "trie/"]);
+    }
+
+    #[test]
+    fn range_matches_filtering_every_value() {
+        let words = sorted_words();
+        let tree = Tree::from_sorted_iter(Match::Exact, Case::Sensitive, false, Allocation::Heap, &words).unwrap();
+        let between = |start: &str, end: &str| {
+            words.iter().filter(|word| start <= word.as_str() && word.as_str() < end).cloned().collect::<Vec<_>>()
+        };
+
+        assert_eq!(tree.range("1".."2").collect::<Vec<_>>(), between("1", "2"));
+        assert_eq!(tree.range("12zz".."3").collect::<Vec<_>>(), between("12zz", "3"));
+        //bounds that aren't values themselves
+        assert_eq!(tree.range("10a".."1:").collect::<Vec<_>>(), between("10a", "1:"));
+        assert_eq!(tree.range("1".."1").count(), 0);
+        assert_eq!(tree.range(..).count(), words.len());
+        assert_eq!(tree.range("l"..).count(), 0);
+
+        let inclusive = tree.range((Bound::Excluded("10"), Bound::Included("11"))).collect::<Vec<_>>();
+        assert_eq!(inclusive, vec!["10zz", "11"]);
+    }
+
+    #[test]
+    fn to_dot_labels_nodes_and_edges() {
+        let mut tree = Tree::new(Match::Exact, Case::Sensitive, false);
+        assert_eq!(tree.to_dot(3), "digraph tree {\n}\n");
+        for word in &["a", "ab", "b\"", "bcd"] {
+            tree.add(word);
+        }
+
+        let expected = r#"digraph tree {
+    node [shape=box, fontname="monospace"];
+    n0 [label="Node4\na b"];
+    n0 -> n1 [label="a"];
+    n0 -> n2 [label="b"];
+    n1 [label="Node4\nb", peripheries=2];
+    n1 -> n3 [label="b"];
+    n3 [label="Node0", peripheries=2];
+    n2 [label="Node4\n0x22 c"];
+    n2 -> n4 [label="0x22"];
+    n2 -> n5 [label="c"];
+    n4 [label="Node0", peripheries=2];
+    n5 [label="Node4\nd"];
+    n5_more [label="1 more", shape=plaintext];
+    n5 -> n5_more [style=dashed];
+}
+"#;
+        assert_eq!(tree.to_dot(2), expected);
+    }
+
+    #[test]
+    fn path_describes_each_node_down_to_the_value() {
+        let mut tree = Tree::new(Match::Exact, Case::Insensitve, false);
+        assert!(tree.path("a").is_empty());
+        for word in &["ab", "abc", "abd", "ax", "b"] {
+            tree.add(word);
+        }
+
+        let path = tree.path("AB");
+        let shape = path
+            .iter()
+            .map(|node| (node.key, node.kind, node.size, node.terminal))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            shape,
+            vec![
+                (None, NodeKind::Node4, 2, false),
+                (Some(b'a'), NodeKind::Node4, 2, false),
+                (Some(b'b'), NodeKind::Node4, 2, true),
+            ]
+        );
+        assert_eq!(tree.path("abc").last().unwrap().kind, NodeKind::Node0);
+        //stops at the first missing byte
+        assert_eq!(tree.path("aqz").len(), 2);
+    }
+
+    #[test]
+    fn snapshot_ignores_later_changes() {
+        let mut tree = Tree::new(Match::Exact, Case::Sensitive, false);
+        tree.add("abc");
+        tree.add("abd");
+        let snapshot = tree.snapshot().unwrap();
+        tree.add("abe");
+        tree.remove("abc");
+
+        assert!(snapshot.exists("abc") && snapshot.exists("abd") && !snapshot.exists("abe"));
+        assert!(!tree.exists("abc") && tree.exists("abd") && tree.exists("abe"));
+        assert_eq!(snapshot.iter().collect::<Vec<_>>(), vec!["abc", "abd"]);
+        assert_eq!(snapshot.auto_complete("ab", 1), vec!["abc"]);
+
+        let arena = Tree::with_allocation(Match::Exact, Case::Sensitive, false, Allocation::Arena);
+        assert_eq!(arena.snapshot().err(), Some(SnapshotError));
+    }
+
     // #[bench]
     // fn bench_building_english_dictionary(b :&mut Bencher) {
     //     let mut root = NodeOption::Some(Box::new(Node0::new()));
@@ -0,0 +1,121 @@
+use crate::trie::encode::KeyEncode;
+use crate::trie::enums::{Allocation, Case, Match};
+use crate::trie::tree::Tree;
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+
+//tree of typed keys kept as their order preserving encoding, see KeyEncode
+//keys are decoded again on the way out so iteration follows the key type's own order
+pub struct TypedTree<K> {
+    tree: Tree,
+    keys: PhantomData<K>,
+}
+
+impl<K: KeyEncode> TypedTree<K> {
+    pub fn new() -> Self {
+        TypedTree::with_allocation(Allocation::Heap)
+    }
+
+    pub fn with_allocation(allocation: Allocation) -> Self {
+        TypedTree {
+            tree: Tree::with_allocation(Match::Exact, Case::Sensitive, false, allocation),
+            keys: PhantomData,
+        }
+    }
+
+    pub fn insert(&mut self, key: &K) {
+        self.tree.insert_bytes(&key.to_key())
+    }
+
+    //returns whether the key was in the tree
+    pub fn remove(&mut self, key: &K) -> bool {
+        self.tree.remove_bytes(&key.to_key())
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.tree.contains_bytes(&key.to_key())
+    }
+
+    //every key in order
+    pub fn iter(&self) -> impl Iterator<Item = K> + '_ {
+        self.tree.iter_bytes().map(decode)
+    }
+
+    //keys within range in order
+    pub fn range(&self, range: impl RangeBounds<K>) -> impl Iterator<Item = K> + '_ {
+        let encode = |bound: Bound<&K>| bound.map(KeyEncode::to_key);
+        self.tree
+            .bounded(encode(range.start_bound()), encode(range.end_bound()))
+            .map(decode)
+    }
+
+    //keys whose leading fields are prefix, e.g. every (tenant, timestamp, name) for one tenant
+    //prefix is encoded on its own so it has to be made of whole fields
+    pub fn starting_with<P: KeyEncode>(&self, prefix: &P) -> impl Iterator<Item = K> + '_ {
+        self.tree.prefix_bytes(&prefix.to_key()).map(decode)
+    }
+}
+
+impl<K: KeyEncode> Default for TypedTree<K> {
+    fn default() -> Self {
+        TypedTree::new()
+    }
+}
+
+//every key in the tree went in through to_key
+fn decode<K: KeyEncode>(key: Vec<u8>) -> K {
+    K::from_key(&key).expect("typed tree holds a key that doesn't decode")
+}
+
+#[cfg(test)]
+mod test {
+    use super::TypedTree;
+    use crate::trie::enums::Allocation;
+
+    #[test]
+    fn numbers_iterate_in_numeric_order() {
+        let mut tree = TypedTree::new();
+        let values = [3.5, -0.25, 1e10, -7e3, 0.0, f64::NEG_INFINITY];
+        for value in &values {
+            tree.insert(value);
+        }
+        assert!(tree.contains(&-0.25) && !tree.contains(&0.25));
+
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        assert_eq!(tree.iter().collect::<Vec<_>>(), sorted);
+        assert_eq!(tree.range(-1.0..=3.5).collect::<Vec<_>>(), vec![-0.25, 0.0, 3.5]);
+
+        let mut tree = TypedTree::with_allocation(Allocation::Arena);
+        for value in [-5i64, 300, i64::MIN, 0, -300] {
+            tree.insert(&value);
+        }
+        assert!(tree.remove(&0) && !tree.remove(&0));
+        assert_eq!(tree.iter().collect::<Vec<_>>(), vec![i64::MIN, -300, -5, 300]);
+    }
+
+    #[test]
+    fn composite_keys_group_by_leading_fields() {
+        let mut tree = TypedTree::<(u32, u64, String)>::new();
+        let rows = [
+            (2, 10, "b"),
+            (1, 20, "a"),
+            (1, 3, "z\0"),
+            (2, 10, "a"),
+            (1, 3, "z"),
+            (10, 0, ""),
+        ];
+        for (tenant, timestamp, name) in &rows {
+            tree.insert(&(*tenant, *timestamp, String::from(*name)));
+        }
+
+        let mut sorted = rows.iter().map(|(t, ts, n)| (*t, *ts, String::from(*n))).collect::<Vec<_>>();
+        sorted.sort();
+        assert_eq!(tree.iter().collect::<Vec<_>>(), sorted);
+
+        let tenant = tree.starting_with(&2u32).map(|(_, _, name)| name).collect::<Vec<_>>();
+        assert_eq!(tenant, vec!["a", "b"]);
+        assert_eq!(tree.starting_with(&(1u32, 3u64)).count(), 2);
+        assert_eq!(tree.starting_with(&3u32).count(), 0);
+    }
+}
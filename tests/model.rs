@@ -0,0 +1,156 @@
+//random sequences of operations run against both a Tree and a BTreeSet, comparing every step
+//failures shrink to the shortest sequence of the smallest keys that still disagrees
+use fast_search::trie::enums::{Allocation, Case, Match, Occurrences};
+use fast_search::trie::tree::Tree;
+use proptest::prelude::*;
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone)]
+enum Op {
+    Add(String),
+    Remove(String),
+    Exists(String),
+    Iter,
+    Range(String, String),
+    Complete(String, usize),
+    InsertBytes(Vec<u8>),
+    RemoveBytes(Vec<u8>),
+    ContainsBytes(Vec<u8>),
+    IterBytes,
+    RangeBytes(Vec<u8>, Vec<u8>),
+    PrefixBytes(Vec<u8>),
+    PrefixesOf(Vec<u8>),
+    SuffixBytes(Vec<u8>),
+    InfixBytes(Vec<u8>),
+    FindAllIn(Vec<u8>),
+}
+
+//short keys over a few characters share long prefixes and collide often enough for removes
+//to hit, single characters from the whole ascii range fill nodes up to Node256
+//the multi byte characters share their leading bytes and \0 is an ordinary byte
+fn key() -> impl Strategy<Value = String> {
+    let narrow = prop::collection::vec(prop::sample::select(vec!['a', 'b', '\0', 'é', 'ê']), 0..5);
+    let wide = prop::collection::vec(prop::char::range('\0', '\x7f'), 1..3);
+    prop_oneof![narrow, wide].prop_map(|chars| chars.into_iter().collect())
+}
+
+//the same shapes over raw bytes, high bytes aren't valid utf-8 on their own
+fn bytes() -> impl Strategy<Value = Vec<u8>> {
+    let narrow = prop::collection::vec(prop::sample::select(vec![0u8, 1, b'a', 0xc3, 0xff]), 0..5);
+    let wide = prop::collection::vec(any::<u8>(), 1..3);
+    prop_oneof![narrow, wide]
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        4 => key().prop_map(Op::Add),
+        2 => key().prop_map(Op::Remove),
+        2 => key().prop_map(Op::Exists),
+        1 => Just(Op::Iter),
+        1 => (key(), key()).prop_map(|(start, end)| Op::Range(start, end)),
+        1 => (key(), 0..5usize).prop_map(|(prefix, results)| Op::Complete(prefix, results)),
+        4 => bytes().prop_map(Op::InsertBytes),
+        2 => bytes().prop_map(Op::RemoveBytes),
+        2 => bytes().prop_map(Op::ContainsBytes),
+        1 => Just(Op::IterBytes),
+        1 => (bytes(), bytes()).prop_map(|(start, end)| Op::RangeBytes(start, end)),
+        1 => bytes().prop_map(Op::PrefixBytes),
+        1 => bytes().prop_map(Op::PrefixesOf),
+        1 => bytes().prop_map(Op::SuffixBytes),
+        1 => bytes().prop_map(Op::InfixBytes),
+        1 => prop::collection::vec(bytes(), 0..4).prop_map(|text| Op::FindAllIn(text.concat())),
+    ]
+}
+
+//keys are compared as bytes, the &str methods see them with invalid utf-8 replaced
+//the empty string is never added through Tree::add, only through insert_bytes
+//PrefixPostfix trees answer suffix and infix searches from an index kept up to date on every change
+fn check(matching: Match, allocation: Allocation, ops: Vec<Op>) -> Result<(), TestCaseError> {
+    let mut tree = Tree::with_allocation(matching, Case::Sensitive, false, allocation);
+    let mut model = BTreeSet::<Vec<u8>>::new();
+    let lossy = |keys: Vec<&Vec<u8>>| keys.into_iter().map(|key| String::from_utf8_lossy(key).into_owned()).collect::<Vec<_>>();
+    let between = |model: &BTreeSet<Vec<u8>>, start: &[u8], end: &[u8]| {
+        model.iter().filter(|key| start <= key.as_slice() && key.as_slice() < end).cloned().collect::<Vec<_>>()
+    };
+    let prefixed = |model: &BTreeSet<Vec<u8>>, prefix: &[u8]| {
+        model.range(prefix.to_vec()..).take_while(|key| key.starts_with(prefix)).cloned().collect::<Vec<_>>()
+    };
+    for op in ops {
+        match op {
+            Op::Add(key) => {
+                tree.add(&key);
+                if !key.is_empty() {
+                    model.insert(key.into_bytes());
+                }
+            }
+            Op::Remove(key) => prop_assert_eq!(tree.remove(&key), model.remove(key.as_bytes())),
+            Op::Exists(key) => prop_assert_eq!(tree.exists(&key), model.contains(key.as_bytes())),
+            Op::Iter => prop_assert_eq!(tree.iter().collect::<Vec<_>>(), lossy(model.iter().collect())),
+            Op::Range(start, end) => {
+                let expected = between(&model, start.as_bytes(), end.as_bytes());
+                prop_assert_eq!(tree.range(start.as_str()..end.as_str()).collect::<Vec<_>>(), lossy(expected.iter().collect()));
+            }
+            Op::Complete(prefix, results) => {
+                let expected = prefixed(&model, prefix.as_bytes());
+                prop_assert_eq!(tree.auto_complete(&prefix, results), lossy(expected.iter().take(results).collect()));
+            }
+            Op::InsertBytes(key) => {
+                tree.insert_bytes(&key);
+                model.insert(key);
+            }
+            Op::RemoveBytes(key) => prop_assert_eq!(tree.remove_bytes(&key), model.remove(&key)),
+            Op::ContainsBytes(key) => prop_assert_eq!(tree.contains_bytes(&key), model.contains(&key)),
+            Op::IterBytes => prop_assert_eq!(tree.iter_bytes().collect::<Vec<_>>(), model.iter().cloned().collect::<Vec<_>>()),
+            Op::RangeBytes(start, end) => {
+                let expected = between(&model, &start, &end);
+                prop_assert_eq!(tree.range_bytes(start.as_slice()..end.as_slice()).collect::<Vec<_>>(), expected);
+            }
+            Op::PrefixBytes(prefix) => {
+                prop_assert_eq!(tree.prefix_bytes(&prefix).collect::<Vec<_>>(), prefixed(&model, &prefix));
+            }
+            Op::PrefixesOf(input) => {
+                let expected = model.iter().filter(|key| input.starts_with(key)).collect::<Vec<_>>();
+                prop_assert_eq!(tree.longest_prefix_of_bytes(&input), expected.last().map(|key| key.as_slice()));
+                prop_assert_eq!(tree.prefixes_of_bytes(&input), expected);
+            }
+            Op::SuffixBytes(suffix) => {
+                let expected = model.iter().filter(|key| key.ends_with(&suffix)).cloned().collect::<Vec<_>>();
+                prop_assert_eq!(tree.suffix_bytes(&suffix), expected);
+            }
+            Op::InfixBytes(infix) => {
+                let holds = |key: &[u8]| infix.is_empty() || key.windows(infix.len()).any(|window| window == infix.as_slice());
+                let expected = model.iter().filter(|key| holds(key)).cloned().collect::<Vec<_>>();
+                prop_assert_eq!(tree.infix_bytes(&infix), expected);
+            }
+            Op::FindAllIn(text) => {
+                //every non empty key at every position, shorter first, then greedily the longest from the left
+                let mut overlapping = Vec::new();
+                for start in 0..text.len() {
+                    for key in model.iter().filter(|key| !key.is_empty() && text[start..].starts_with(key)) {
+                        overlapping.push(start..start + key.len());
+                    }
+                }
+                prop_assert_eq!(tree.find_all_in_bytes(&text, Occurrences::Overlapping), overlapping.clone());
+                let (mut leftmost_longest, mut end) = (Vec::new(), 0);
+                for start in 0..text.len() {
+                    if let Some(found) = overlapping.iter().rfind(|found| found.start == start && start >= end) {
+                        end = found.end;
+                        leftmost_longest.push(found.clone());
+                    }
+                }
+                prop_assert_eq!(tree.find_all_in_bytes(&text, Occurrences::LeftmostLongest), leftmost_longest);
+            }
+        }
+        prop_assert_eq!(tree.validate(), Ok(()));
+    }
+    prop_assert_eq!(tree.stats().keys, model.len());
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn tree_behaves_like_a_sorted_set(ops in prop::collection::vec(op(), 0..200)) {
+        check(Match::Exact, Allocation::Heap, ops.clone())?;
+        check(Match::PrefixPostfix, Allocation::Arena, ops)?;
+    }
+}